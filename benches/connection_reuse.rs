@@ -0,0 +1,55 @@
+//! Connection reuse of concurrent history fetches
+//!
+//! Run with `cargo bench --features test-util --bench connection_reuse`.
+//!
+//! A client per request pays a connection setup (TCP, and TLS against TWSE) every time,
+//! while a shared client keeps them pooled, opening up to `BATCH_CONCURRENCY` connections to
+//! the HTTP/1.1 mock server.
+
+use std::time::Instant;
+
+use chrono::Month;
+use twstock::{history::BATCH_CONCURRENCY, mock::MockServer, *};
+
+const REQUESTS: u32 = 48;
+
+fn requests() -> impl Iterator<Item = (Month, u16, Stock)> {
+    (0..REQUESTS).map(|x| {
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        (Month::try_from((x % 12 + 1) as u8).unwrap(), 2021, stock)
+    })
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let server = MockServer::with_fixtures();
+    let start = Instant::now();
+    for (month, year, stock) in requests() {
+        server
+            .client()
+            .history()
+            .fetch(month, year, stock)
+            .await
+            .unwrap();
+    }
+    println!(
+        "client per request: {:>8.2?}, {} connections",
+        start.elapsed(),
+        server.connections()
+    );
+
+    let server = MockServer::with_fixtures();
+    let client = server.client();
+    let start = Instant::now();
+    client.history().fetch_batch(requests()).await.unwrap();
+    println!(
+        "shared client:      {:>8.2?}, {} connections (at most {})",
+        start.elapsed(),
+        server.connections(),
+        BATCH_CONCURRENCY
+    );
+    assert!(server.connections() <= BATCH_CONCURRENCY);
+}
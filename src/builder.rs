@@ -0,0 +1,274 @@
+//! Builder of [`Client`]
+
+use std::{net::SocketAddr, time::Duration};
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE, REFERER},
+    redirect::Policy,
+    Client as HttpClient,
+};
+
+use crate::{retry::RateLimiter, Client, Error};
+
+static BROWSER_USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36";
+
+/// Builder to configure the HTTP client behind [`Client`]
+///
+/// Redirect is not followed by default, so a redirect to a maintenance or error page
+/// surfaces as an error instead of being parsed as data.
+pub struct ClientBuilder {
+    http: reqwest::ClientBuilder,
+    max_concurrency: Option<usize>,
+    rate_limit: Option<(u32, Duration)>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            http: HttpClient::builder().redirect(Policy::none()),
+            max_concurrency: None,
+            rate_limit: None,
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// Create a new builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the redirect policy, e.g. `Policy::limited(10)` to follow redirect
+    pub fn redirect(mut self, policy: Policy) -> Self {
+        self.http = self.http.redirect(policy);
+        self
+    }
+    /// Set the timeout of each request, from connecting until the body is read
+    ///
+    /// Exceeding it is [`Error::Timeout`]. No timeout by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.timeout(timeout);
+        self
+    }
+    /// Set the timeout of connecting only
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.connect_timeout(timeout);
+        self
+    }
+    /// Set the `User-Agent` header of every request
+    ///
+    /// Invalid header value fails [`ClientBuilder::build`].
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http = self.http.user_agent(user_agent.into());
+        self
+    }
+    /// Set the maximum idle connections kept in the pool per host
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.http = self.http.pool_max_idle_per_host(max);
+        self
+    }
+    /// Resolve `host` to `addr` instead of using DNS, e.g. to pin `mis.twse.com.tw` to a
+    /// known-good node
+    ///
+    /// Port `0` of `addr` means the default port of the scheme.
+    pub fn resolve(mut self, host: &str, addr: SocketAddr) -> Self {
+        self.http = self.http.resolve(host, addr);
+        self
+    }
+    /// Limit the number of in-flight requests of the client, across every module
+    ///
+    /// Batch, range and bulk fetches share the limit, so running several of them
+    /// concurrently won't open more than `limit` connections at once. Unlimited by default.
+    pub fn max_concurrency(mut self, limit: usize) -> Self {
+        self.max_concurrency = Some(limit.max(1));
+        self
+    }
+    /// Pace requests of the client to at most `requests` per `per`, across every module
+    ///
+    /// Requests beyond the budget wait client-side instead of tripping the rate limit of
+    /// TWSE, up to `requests` may go in a burst. Retries are paced as well. Unlimited by
+    /// default.
+    pub fn rate_limit(mut self, requests: u32, per: Duration) -> Self {
+        self.rate_limit = Some((requests, per));
+        self
+    }
+    /// Build the client
+    pub fn build(self) -> Result<Client, Error> {
+        let mut client = Client::from_http(self.http.build()?);
+        client.concurrency = self.max_concurrency.map(tokio::sync::Semaphore::new);
+        client.rate_limiter = self
+            .rate_limit
+            .map(|(requests, per)| RateLimiter::new(requests, per));
+        Ok(client)
+    }
+}
+
+impl Client {
+    /// Create a new client with defaults that work against TWSE out of the box
+    ///
+    /// It sets:
+    /// - `User-Agent` of a desktop browser
+    /// - `Referer: https://www.twse.com.tw/`
+    /// - `Accept-Language: zh-TW`
+    /// - 10 seconds connect timeout and 30 seconds request timeout
+    pub fn new_with_region() -> Result<Self, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            REFERER,
+            HeaderValue::from_static("https://www.twse.com.tw/"),
+        );
+        headers.insert(
+            ACCEPT_LANGUAGE,
+            HeaderValue::from_static("zh-TW,zh;q=0.9,en;q=0.8"),
+        );
+        let mut builder = ClientBuilder::new()
+            .user_agent(BROWSER_USER_AGENT)
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(30));
+        builder.http = builder.http.default_headers(headers);
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockServer, Stock, StockKind};
+
+    #[tokio::test]
+    async fn redirect() {
+        let server = MockServer::start();
+        server
+            .redirect("/stock/api/getStockInfo.jsp", "/moved")
+            .respond("/moved", 200, crate::mock::FIXTURES[0].1);
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+
+        let client = server.client();
+        assert!(client.realtime().fetch(stock.clone()).await.is_err());
+        assert_eq!(server.hits("/moved"), 0);
+
+        let client = Client::builder()
+            .redirect(Policy::limited(10))
+            .build()
+            .unwrap()
+            .with_base_url(server.url());
+        client.realtime().fetch(stock).await.unwrap();
+        assert_eq!(server.hits("/moved"), 1);
+    }
+    #[tokio::test]
+    async fn timeout() {
+        // accepted by the backlog but never answered
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = Client::builder()
+            .timeout(Duration::from_millis(1))
+            .user_agent("twstock-test")
+            .build()
+            .unwrap()
+            .with_base_url(format!("http://{}", listener.local_addr().unwrap()));
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let err = client.realtime().fetch(stock).await.unwrap_err();
+        assert!(matches!(err, Error::Timeout(_)), "{:?}", err);
+
+        assert!(Client::builder().user_agent("\n").build().is_err());
+    }
+    #[tokio::test]
+    async fn resolve() {
+        let server = MockServer::with_fixtures();
+        let addr: SocketAddr = server.url().trim_start_matches("http://").parse().unwrap();
+        let client = Client::builder()
+            .resolve("twse.invalid", addr)
+            .build()
+            .unwrap()
+            .with_base_url(format!("http://twse.invalid:{}", addr.port()));
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        client.realtime().fetch(stock).await.unwrap();
+        assert_eq!(server.hits("/stock/api/getStockInfo.jsp"), 1);
+    }
+    #[tokio::test]
+    async fn max_concurrency() {
+        let server = MockServer::with_fixtures();
+        let client = Client::builder()
+            .max_concurrency(1)
+            .build()
+            .unwrap()
+            .with_base_url(server.url());
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let requests = (1..=8).map(|x| (chrono::Month::try_from(x).unwrap(), 2021, stock.clone()));
+        let (history, institutional) = (client.history(), client.institutional());
+        let (batch, range) = tokio::join!(
+            history.fetch_batch(requests),
+            institutional.fetch_range(
+                chrono::NaiveDate::from_ymd_opt(2024, 7, 15).unwrap(),
+                chrono::NaiveDate::from_ymd_opt(2024, 7, 19).unwrap(),
+                stock.clone(),
+            )
+        );
+        assert_eq!(batch.unwrap().len(), 8);
+        assert_eq!(range.unwrap().len(), 5);
+        // one request at a time reuses a single connection
+        assert_eq!(server.connections(), 1);
+    }
+    #[tokio::test]
+    async fn rate_limit() {
+        let server = MockServer::with_fixtures();
+        let client = Client::builder()
+            .rate_limit(2, Duration::from_millis(200))
+            .build()
+            .unwrap()
+            .with_base_url(server.url());
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let start = std::time::Instant::now();
+        for _ in 0..2 {
+            client.realtime().fetch(stock.clone()).await.unwrap();
+        }
+        // within the burst
+        assert!(start.elapsed() < Duration::from_millis(100));
+        for _ in 0..2 {
+            client.realtime().fetch(stock.clone()).await.unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+    #[tokio::test]
+    async fn ping() {
+        let server = MockServer::start();
+        let path = "/stock/api/getStockInfo.jsp";
+        server
+            .enqueue(path, 200, "")
+            .enqueue(path, 200, "")
+            .enqueue(path, 503, "")
+            .enqueue(path, 404, "");
+        let client = Client::builder()
+            .rate_limit(1, Duration::from_millis(200))
+            .build()
+            .unwrap()
+            .with_base_url(server.url());
+        let start = std::time::Instant::now();
+        client.ping_latency().await.unwrap();
+        client.ping_latency().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert!(matches!(
+            client.ping_latency().await,
+            Err(Error::ServerError(_))
+        ));
+        assert!(matches!(
+            client.ping_latency().await,
+            Err(Error::UnexpectedStatus(_))
+        ));
+        assert_eq!(server.hits(path), 4);
+    }
+}
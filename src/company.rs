@@ -0,0 +1,137 @@
+//! TWSE listed company basic information API
+
+use serde::{Deserialize, Serialize};
+
+use crate::{price_from, Client, Error, Family, MediaType, Price, Stock, StockKind};
+
+pub(crate) static ENDPOINT: &str = "https://openapi.twse.com.tw/v1/opendata/t187ap03_L";
+/// keys of each `t187ap03_L` record this module reads
+pub(crate) const FIELDS: [&str; 4] = [
+    "公司代號",
+    "公司名稱",
+    "公司簡稱",
+    "已發行普通股數或TDR原股發行股數",
+];
+
+/// Basic information of a listed company
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompanyInfo {
+    /// Stock identifier
+    pub id: Stock,
+    /// full chinese name
+    pub name: String,
+    /// chinese name abbreviation
+    pub abbr: String,
+    /// issued common shares, `None` if not disclosed
+    pub issued_shares: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawCompany {
+    #[serde(rename = "公司代號")]
+    code: String,
+    #[serde(rename = "公司名稱", default)]
+    name: String,
+    #[serde(rename = "公司簡稱", default)]
+    abbr: String,
+    #[serde(rename = "已發行普通股數或TDR原股發行股數", default)]
+    issued_shares: String,
+}
+
+impl TryFrom<RawCompany> for CompanyInfo {
+    type Error = Error;
+
+    fn try_from(value: RawCompany) -> Result<Self, Self::Error> {
+        Ok(CompanyInfo {
+            id: Stock {
+                kind: StockKind::Live,
+                code: value
+                    .code
+                    .trim()
+                    .parse()
+                    .map_err(|_| Error::incompatible())?,
+            },
+            name: value.name,
+            abbr: value.abbr,
+            issued_shares: value.issued_shares.replace(',', "").trim().parse().ok(),
+        })
+    }
+}
+
+fn parse_companies(body: &[u8]) -> Result<Vec<CompanyInfo>, Error> {
+    Ok(serde_json::from_slice::<Vec<RawCompany>>(body)
+        .map_err(|_| Error::incompatible())?
+        .into_iter()
+        // skip non-numeric code
+        .filter_map(|x| x.try_into().ok())
+        .collect())
+}
+
+/// newtype wrapper for the [`Client`] facilitating company api
+pub struct Company<'a>(&'a Client);
+
+impl Client {
+    /// Get the company API client
+    pub fn company(&self) -> Company<'_> {
+        Company(self)
+    }
+    /// Compute market capitalization (issued shares × realtime price)
+    ///
+    /// return `None` if issued shares is not available or there's no trade yet today, only
+    /// listed ([`StockKind::Live`]) company is covered
+    pub async fn market_cap(&self, stock: Stock) -> Result<Option<Price>, Error> {
+        let Some(issued_shares) = self
+            .company()
+            .fetch(stock.clone())
+            .await?
+            .and_then(|x| x.issued_shares)
+        else {
+            return Ok(None);
+        };
+        let price = self.realtime().fetch(stock).await?.price;
+        Ok(price.map(|price| price_from(issued_shares) * price))
+    }
+}
+
+impl Company<'_> {
+    /// Fetch basic information of every listed company
+    pub async fn fetch_all(&self) -> Result<Vec<CompanyInfo>, Error> {
+        let body = self
+            .0
+            .get(Family::Report, ENDPOINT, &[], MediaType::Json)
+            .await?;
+        parse_companies(&body).map_err(|err| err.with_body(&body))
+    }
+    /// Fetch basic information of a company
+    ///
+    /// return `None` if the company is not listed
+    pub async fn fetch(&self, stock: Stock) -> Result<Option<CompanyInfo>, Error> {
+        if stock.kind != StockKind::Live {
+            return Ok(None);
+        }
+        Ok(self.fetch_all().await?.into_iter().find(|x| x.id == stock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let data = parse_companies(include_bytes!("../test/t187ap03_L.json")).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1].abbr, "台積電");
+        assert_eq!(data[1].issued_shares, Some(25932370458));
+        assert_eq!(data[0].issued_shares, None);
+    }
+    #[test]
+    fn schema() {
+        let records: Vec<serde_json::Value> =
+            serde_json::from_slice(include_bytes!("../test/t187ap03_L.json")).unwrap();
+        for key in FIELDS {
+            assert!(records[1].get(key).is_some(), "missing key {}", key);
+        }
+    }
+}
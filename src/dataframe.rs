@@ -0,0 +1,123 @@
+//! Conversion of fetched data into a polars [`DataFrame`]
+
+use polars::prelude::*;
+
+use crate::{history::DailyData, list::StockInfo, price_to_f64};
+
+/// Collect records into a [`DataFrame`], one row per record
+pub trait ToDataFrame {
+    /// Build the columns, see the implementation for their names
+    fn to_dataframe(&self) -> PolarsResult<DataFrame>;
+}
+
+/// Columns `date`, `volume`, `turnover`, `open_price`, `high_price`, `low_price`,
+/// `close_price`, `diff` and `transaction`
+///
+/// Prices and turnover are `Float64` even with the `decimal` feature.
+impl ToDataFrame for [DailyData] {
+    fn to_dataframe(&self) -> PolarsResult<DataFrame> {
+        macro_rules! column {
+            ($f:ident) => {
+                Column::new(
+                    stringify!($f).into(),
+                    self.iter().map(|x| x.$f).collect::<Vec<_>>(),
+                )
+            };
+            ($f:ident, Price) => {
+                Column::new(
+                    stringify!($f).into(),
+                    self.iter().map(|x| price_to_f64(x.$f)).collect::<Vec<_>>(),
+                )
+            };
+        }
+        DataFrame::new(vec![
+            column!(date),
+            column!(volume),
+            column!(turnover, Price),
+            column!(open_price, Price),
+            column!(high_price, Price),
+            column!(low_price, Price),
+            column!(close_price, Price),
+            column!(diff, Price),
+            column!(transaction),
+        ])
+    }
+}
+
+/// Columns `code`, `over_the_counter`, `abbr`, `release_date` and `industry` (chinese name)
+impl ToDataFrame for [StockInfo] {
+    fn to_dataframe(&self) -> PolarsResult<DataFrame> {
+        DataFrame::new(vec![
+            Column::new(
+                "code".into(),
+                self.iter().map(|x| x.id.code).collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "over_the_counter".into(),
+                self.iter()
+                    .map(|x| x.id.kind == crate::StockKind::OverTheCounter)
+                    .collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "abbr".into(),
+                self.iter().map(|x| x.abbr.as_str()).collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "release_date".into(),
+                self.iter().map(|x| x.release_date).collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "industry".into(),
+                self.iter()
+                    .map(|x| x.industry.as_chinese())
+                    .collect::<Vec<_>>(),
+            ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{list::Industry, price, Stock, StockKind};
+
+    #[test]
+    fn daily_data() {
+        let data = vec![
+            DailyData {
+                date: NaiveDate::from_ymd_opt(2021, 1, 4).unwrap(),
+                volume: 39489959,
+                close_price: price("536"),
+                ..Default::default()
+            };
+            3
+        ];
+        let df = data.to_dataframe().unwrap();
+        assert_eq!(df.shape(), (3, 9));
+        assert_eq!(df.column("date").unwrap().dtype(), &DataType::Date);
+        assert_eq!(
+            df.column("volume").unwrap().u64().unwrap().get(0),
+            Some(39489959)
+        );
+    }
+    #[test]
+    fn stock_info() {
+        let list = [StockInfo {
+            id: Stock {
+                kind: StockKind::Live,
+                code: 2330,
+            },
+            abbr: "台積電".to_string(),
+            release_date: NaiveDate::from_ymd_opt(1994, 9, 5).unwrap(),
+            industry: Industry::Semiconductor,
+        }];
+        let df = list.to_dataframe().unwrap();
+        assert_eq!(df.shape(), (1, 5));
+        assert_eq!(
+            df.column("industry").unwrap().str().unwrap().get(0),
+            Some("半導體業")
+        );
+    }
+}
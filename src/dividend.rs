@@ -0,0 +1,151 @@
+//! TWSE ex-rights and ex-dividend (除權息) API
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::table::{self, cell, Report};
+use crate::{is_no_data, parse_roc_date, Client, Error, Family, MediaType, Stock, StockKind};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/exchangeReport/TWT49U";
+/// column headers of `TWT49U` this module reads
+pub(crate) const FIELDS: [&str; 6] = [
+    "資料日期",
+    "股票代號",
+    "除權息前收盤價",
+    "權值+息值",
+    "權/息",
+    "減除股利參考價",
+];
+
+/// Ex-rights or ex-dividend of a stock, amounts in NT dollars per share
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Dividend {
+    pub stock: Stock,
+    /// Be noted that `ex_date` is `UTC+8`
+    pub ex_date: NaiveDate,
+    /// cash dividend (息值)
+    pub cash_dividend: f64,
+    /// value of stock dividend (權值), the price drop it causes rather than the share ratio
+    pub stock_dividend: f64,
+}
+
+/// Parse ROC date either like `113/07/15` or `113年07月15日`
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.replace(['年', '月'], "/");
+    parse_roc_date(value.trim().trim_end_matches('日'))
+}
+
+/// Split `total` of `kind` (`息`, `權` or `權息`) into cash and stock dividend
+///
+/// `權息` only has the sum published, the cash part is the close minus the reference price
+/// excluding dividend.
+fn split(kind: &str, total: f64, close: Option<f64>, excluded: Option<f64>) -> Option<(f64, f64)> {
+    match kind.trim() {
+        "息" => Some((total, 0.0)),
+        "權" => Some((0.0, total)),
+        "權息" => {
+            // round off the float error of the subtraction, TWSE quotes at most 4 decimals
+            let cash = ((close? - excluded?) * 10_000.0).round() / 10_000.0;
+            Some((cash, total - cash))
+        }
+        _ => None,
+    }
+}
+
+/// parse the report and return every ex-rights or ex-dividend in it
+fn parse_report(body: &[u8]) -> Result<Vec<Dividend>, Error> {
+    let report: Report = match table::parse_json(body) {
+        Err(Error::StatMessage(stat)) if is_no_data(&stat) => return Ok(Vec::new()),
+        x => x?,
+    };
+    let [date, code, close, total, kind, excluded] = report.columns(FIELDS)?;
+    table::listed_rows(&report.data, code, StockKind::Live)
+        .map(|row| {
+            let (stock, row) = row?;
+            let text = |i: usize| row.get(i).and_then(Value::as_str).map(str::trim);
+            let number = |i: usize| row.get(i).and_then(table::parse_cell);
+            let ex_date = text(date)
+                .and_then(parse_date)
+                .ok_or(Error::incompatible())?;
+            let (cash_dividend, stock_dividend) = split(
+                text(kind).unwrap_or_default(),
+                cell(row, total)?,
+                number(close),
+                number(excluded),
+            )
+            .ok_or(Error::incompatible())?;
+            Ok(Dividend {
+                stock,
+                ex_date,
+                cash_dividend,
+                stock_dividend,
+            })
+        })
+        .collect()
+}
+
+/// newtype wrapper for the [`Client`] facilitating ex-rights and ex-dividend api
+pub struct DividendReport<'a>(&'a Client);
+
+impl Client {
+    /// Get the ex-rights and ex-dividend API client
+    pub fn dividend(&self) -> DividendReport<'_> {
+        DividendReport(self)
+    }
+}
+
+impl DividendReport<'_> {
+    /// Fetch every listed stock going ex-rights or ex-dividend on a specific day
+    ///
+    /// Empty if there's none, including non-trading day.
+    pub async fn fetch(&self, date: NaiveDate) -> Result<Vec<Dividend>, Error> {
+        let date = date.format("%Y%m%d").to_string();
+        let body = self
+            .0
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[("response", "json"), ("strDate", &date), ("endDate", &date)],
+                MediaType::Json,
+            )
+            .await?;
+        parse_report(&body).map_err(|err| err.with_body(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::tests::{date, stock};
+
+    #[test]
+    fn parse() {
+        let data = parse_report(include_bytes!("../test/TWT49U.json")).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0].stock, stock());
+        assert_eq!(data[0].ex_date, date());
+        assert_eq!(data[0].cash_dividend, 4.0);
+        assert_eq!(data[0].stock_dividend, 0.0);
+        assert_eq!(data[1].stock_dividend, 1.5);
+        assert_eq!(data[2].cash_dividend, 1.0);
+        assert!((data[2].stock_dividend - 0.87).abs() < 1e-9);
+        assert_eq!(
+            parse_report(r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#.as_bytes()).unwrap(),
+            []
+        );
+    }
+    #[test]
+    fn roc_date() {
+        assert_eq!(parse_date("113年07月15日"), Some(date()));
+        assert_eq!(parse_date("113/07/15"), Some(date()));
+    }
+    #[tokio::test]
+    async fn fetch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let data = server.client().dividend().fetch(date()).await.unwrap();
+        assert_eq!(data[0].cash_dividend, 4.0);
+    }
+}
@@ -0,0 +1,118 @@
+//! CSV export of fetched data for spreadsheet
+
+use std::io::Write;
+
+use crate::{history::DailyData, list::StockInfo, Error, StockKind};
+
+/// Write `data` as CSV with a header row, columns `date` (ISO-8601), `volume`, `turnover`,
+/// `open_price`, `high_price`, `low_price`, `close_price`, `diff` and `transaction`
+pub fn write_daily_csv<W: Write>(w: W, data: &[DailyData]) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(w);
+    writer
+        .write_record([
+            "date",
+            "volume",
+            "turnover",
+            "open_price",
+            "high_price",
+            "low_price",
+            "close_price",
+            "diff",
+            "transaction",
+        ])
+        .map_err(std::io::Error::from)?;
+    for x in data {
+        writer
+            .write_record([
+                x.date.format("%Y-%m-%d").to_string(),
+                x.volume.to_string(),
+                x.turnover.to_string(),
+                x.open_price.to_string(),
+                x.high_price.to_string(),
+                x.low_price.to_string(),
+                x.close_price.to_string(),
+                x.diff.to_string(),
+                x.transaction.to_string(),
+            ])
+            .map_err(std::io::Error::from)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write `data` as CSV with a header row, columns `code`, `over_the_counter`, `abbr`,
+/// `release_date` (ISO-8601) and `industry` (chinese name)
+pub fn write_stock_list_csv<W: Write>(w: W, data: &[StockInfo]) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(w);
+    writer
+        .write_record([
+            "code",
+            "over_the_counter",
+            "abbr",
+            "release_date",
+            "industry",
+        ])
+        .map_err(std::io::Error::from)?;
+    for x in data {
+        writer
+            .write_record([
+                x.id.code.to_string().as_str(),
+                (x.id.kind == StockKind::OverTheCounter)
+                    .to_string()
+                    .as_str(),
+                x.abbr.as_str(),
+                x.release_date.format("%Y-%m-%d").to_string().as_str(),
+                x.industry.as_chinese(),
+            ])
+            .map_err(std::io::Error::from)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{list::Industry, price, Stock};
+
+    #[test]
+    fn daily_data() {
+        let data = vec![DailyData {
+            date: NaiveDate::from_ymd_opt(2021, 1, 4).unwrap(),
+            volume: 39489959,
+            turnover: price("21127094445"),
+            open_price: price("530"),
+            high_price: price("540"),
+            low_price: price("528"),
+            close_price: price("536"),
+            diff: price("6"),
+            transaction: 42245,
+            ..Default::default()
+        }];
+        let mut buf = Vec::new();
+        write_daily_csv(&mut buf, &data).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "date,volume,turnover,open_price,high_price,low_price,close_price,diff,transaction\n\
+             2021-01-04,39489959,21127094445,530,540,528,536,6,42245\n"
+        );
+    }
+    #[test]
+    fn stock_list() {
+        let data = vec![StockInfo {
+            id: Stock::otc(6488),
+            abbr: "環球晶".to_string(),
+            release_date: NaiveDate::from_ymd_opt(2015, 9, 25).unwrap(),
+            industry: Industry::Semiconductor,
+        }];
+        let mut buf = Vec::new();
+        write_stock_list_csv(&mut buf, &data).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "code,over_the_counter,abbr,release_date,industry\n\
+             6488,true,環球晶,2015-09-25,半導體業\n"
+        );
+    }
+}
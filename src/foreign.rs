@@ -0,0 +1,155 @@
+//! TWSE foreign and mainland investor (外資及陸資) shareholding API
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::table::{self, cell, DailyCache, Report};
+use crate::{Client, Error, Family, MediaType, Stock, StockKind};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/rwd/zh/fund/MI_QFIIS";
+/// column headers of `MI_QFIIS` this module reads
+pub(crate) const FIELDS: [&str; 5] = [
+    "證券代號",
+    "發行股數",
+    "全體外資及陸資持有股數",
+    "全體外資及陸資持股比率",
+    "外資及陸資共用法令投資上限比率",
+];
+
+/// Foreign and mainland investor shareholding of a stock at the end of a day
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ForeignHolding {
+    /// Be noted that `date` is `UTC+8`
+    pub date: NaiveDate,
+    /// shares issued
+    pub issued_shares: u64,
+    /// shares held by all foreign and mainland investors
+    pub foreign_held_shares: u64,
+    /// percentage of issued shares held, e.g. `73.52` for `73.52%`
+    pub foreign_held_ratio: f64,
+    /// percentage foreign investors are allowed to hold by law, usually `100`
+    pub foreign_ceiling_ratio: f64,
+}
+
+/// parse the report and return the holding of every stock of the day
+fn parse_report(body: &[u8], date: NaiveDate) -> Result<Vec<(Stock, ForeignHolding)>, Error> {
+    let report: Report = table::parse_daily(body)?;
+    let [code, issued, held, ratio, ceiling] = report.columns(FIELDS)?;
+    table::listed_rows(&report.data, code, StockKind::Live)
+        .map(|row| {
+            let (stock, row) = row?;
+            let holding = ForeignHolding {
+                date,
+                issued_shares: cell(row, issued)?,
+                foreign_held_shares: cell(row, held)?,
+                foreign_held_ratio: cell(row, ratio)?,
+                foreign_ceiling_ratio: cell(row, ceiling)?,
+            };
+            Ok((stock, holding))
+        })
+        .collect()
+}
+
+/// Wrapper of the [`Client`] facilitating foreign shareholding api
+///
+/// Each day's report is cached, see [daily reports](crate#daily-reports).
+pub struct Foreign<'a> {
+    client: &'a Client,
+    cache: DailyCache<ForeignHolding>,
+}
+
+impl Client {
+    /// Get the foreign shareholding API client
+    pub fn foreign(&self) -> Foreign<'_> {
+        Foreign {
+            client: self,
+            cache: DailyCache::new(),
+        }
+    }
+}
+
+impl Foreign<'_> {
+    /// Fetch the holding of every listed stock on a specific day
+    ///
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch_all(&self, date: NaiveDate) -> Result<Vec<(Stock, ForeignHolding)>, Error> {
+        self.cache.get_or_fetch(date, self.fetch_report(date)).await
+    }
+    /// Fetch the holding of a stock on a specific day
+    ///
+    /// A stock not in the report has every figure zero.
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch(&self, date: NaiveDate, stock: Stock) -> Result<ForeignHolding, Error> {
+        Ok(
+            table::find(self.fetch_all(date).await?, &stock).unwrap_or(ForeignHolding {
+                date,
+                ..Default::default()
+            }),
+        )
+    }
+    async fn fetch_report(&self, date: NaiveDate) -> Result<Vec<(Stock, ForeignHolding)>, Error> {
+        let body = self
+            .client
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &date.format("%Y%m%d").to_string()),
+                    ("selectType", "ALLBUT0999"),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+        parse_report(&body, date).map_err(|err| err.with_body(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::tests::{date, stock};
+
+    #[test]
+    fn parse() {
+        let data = parse_report(include_bytes!("../test/MI_QFIIS.json"), date()).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].0, stock());
+        assert_eq!(
+            data[0].1,
+            ForeignHolding {
+                date: date(),
+                issued_shares: 25932070634,
+                foreign_held_shares: 19066301852,
+                foreign_held_ratio: 73.52,
+                foreign_ceiling_ratio: 100.0,
+            }
+        );
+        assert!(matches!(
+            parse_report(
+                r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#.as_bytes(),
+                date()
+            ),
+            Err(Error::MarketClosed)
+        ));
+    }
+    #[tokio::test]
+    async fn fetch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let foreign = client.foreign();
+        let data = foreign.fetch(date(), stock()).await.unwrap();
+        assert_eq!(data.foreign_held_ratio, 73.52);
+        let missing = foreign.fetch(date(), Stock::live(9999)).await.unwrap();
+        assert_eq!(
+            missing,
+            ForeignHolding {
+                date: date(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(server.hits("/rwd/zh/fund/MI_QFIIS"), 1);
+    }
+}
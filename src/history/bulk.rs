@@ -0,0 +1,717 @@
+//! TWSE whole-market daily report (`MI_INDEX`)
+
+use std::fmt;
+use std::io::Write;
+
+use chrono::{Month, Months, NaiveDate};
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{json, Value};
+
+use super::{parse_signed, DailyData, History, PriceDirection};
+use crate::table::{self, parse_cell, Tables};
+use crate::{
+    is_no_data, strip_csv_guard, weekdays, Error, Family, MediaType, Price, Stock, StockKind,
+};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/exchangeReport/MI_INDEX";
+/// column headers of the `MI_INDEX` stock table this module understands
+pub(crate) const FIELDS: [&str; 10] = [
+    "證券代號",
+    "成交股數",
+    "成交筆數",
+    "成交金額",
+    "開盤價",
+    "最高價",
+    "最低價",
+    "收盤價",
+    "漲跌(+/-)",
+    "漲跌價差",
+];
+
+pub(crate) static TPEX_ENDPOINT: &str = "https://www.tpex.org.tw/www/zh-tw/afterTrading/dailyQ";
+/// column headers of the TPEx daily quote table this module understands
+pub(crate) const TPEX_FIELDS: [&str; 9] = [
+    "代號",
+    "成交股數",
+    "成交筆數",
+    "成交金額(元)",
+    "開盤",
+    "最高",
+    "最低",
+    "收盤",
+    "漲跌",
+];
+
+/// Exchange covered by [`History::fetch_all_in`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Market {
+    /// listed stocks only ([`StockKind::Live`])
+    #[default]
+    Twse,
+    /// OTC stocks only ([`StockKind::OverTheCounter`])
+    Tpex,
+    /// both exchanges, TWSE first
+    Both,
+}
+
+/// Output format of [`History::fetch_all_to_writer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// comma separated values with a header row
+    Csv,
+    /// one JSON object per line
+    Ndjson,
+}
+
+/// text content of a cell like `<p style= color:red>+</p>`
+pub(crate) fn strip_tags(value: &str) -> String {
+    let mut inside = false;
+    value
+        .chars()
+        .filter(|c| match c {
+            '<' => {
+                inside = true;
+                false
+            }
+            '>' => {
+                inside = false;
+                false
+            }
+            _ => !inside,
+        })
+        .collect()
+}
+
+/// map a row by the [`columns`](table::columns) of [`FIELDS`], `None` for non-numeric code or
+/// untraded stock
+fn map(
+    columns: &[usize; FIELDS.len()],
+    date: NaiveDate,
+    row: &[Value],
+) -> Option<(Stock, DailyData)> {
+    let get = |i: usize| row.get(columns[i]);
+    let code = strip_csv_guard(get(0)?.as_str()?.trim());
+    if !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let diff: Price = parse_cell(get(9)?)?;
+    let sign = strip_tags(get(8)?.as_str()?).trim().to_string();
+    let direction = PriceDirection::from_token(&sign);
+    let diff = match direction {
+        PriceDirection::Down => -diff,
+        _ => diff,
+    };
+    let stock = Stock {
+        kind: StockKind::Live,
+        code: code.parse().ok()?,
+    };
+    let data = DailyData {
+        date,
+        volume: parse_cell(get(1)?)?,
+        turnover: parse_cell(get(3)?)?,
+        open_price: parse_cell(get(4)?)?,
+        high_price: parse_cell(get(5)?)?,
+        low_price: parse_cell(get(6)?)?,
+        close_price: parse_cell(get(7)?)?,
+        diff,
+        transaction: parse_cell(get(2)?)?,
+        direction,
+        sign,
+    };
+    Some((stock, data))
+}
+
+/// map a row by the [`columns`](table::columns) of [`TPEX_FIELDS`], sign is part of the diff
+fn map_tpex(
+    columns: &[usize; TPEX_FIELDS.len()],
+    date: NaiveDate,
+    row: &[Value],
+) -> Option<(Stock, DailyData)> {
+    let get = |i: usize| row.get(columns[i]);
+    let code = get(0)?.as_str()?.trim();
+    if !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (sign, direction, diff) = parse_signed(get(8)?.as_str()?)?;
+    let stock = Stock {
+        kind: StockKind::OverTheCounter,
+        code: code.parse().ok()?,
+    };
+    let data = DailyData {
+        date,
+        volume: parse_cell(get(1)?)?,
+        turnover: parse_cell(get(3)?)?,
+        open_price: parse_cell(get(4)?)?,
+        high_price: parse_cell(get(5)?)?,
+        low_price: parse_cell(get(6)?)?,
+        close_price: parse_cell(get(7)?)?,
+        diff,
+        transaction: parse_cell(get(2)?)?,
+        direction,
+        sign,
+    };
+    Some((stock, data))
+}
+
+/// Callback of [`stream_report`], deserializing stops on its error
+type Sink<'a> = &'a mut dyn FnMut(Stock, DailyData) -> Result<(), Error>;
+
+/// State of [`stream_report`] shared by the visitors of each level of the report
+struct Stream<'a> {
+    date: NaiveDate,
+    sink: Sink<'a>,
+    stat: Option<String>,
+    /// whether the report has `tables` at all, a rejected request only has `stat`
+    has_tables: bool,
+    /// whether the stock table was found, later tables are skipped
+    found: bool,
+    count: usize,
+    /// error of `sink`, serde only carries a message
+    failed: Option<Error>,
+}
+
+impl Stream<'_> {
+    fn emit<E: de::Error>(
+        &mut self,
+        columns: &[usize; FIELDS.len()],
+        row: &[Value],
+    ) -> Result<(), E> {
+        let Some((stock, data)) = map(columns, self.date, row) else {
+            return Ok(());
+        };
+        if let Err(err) = (self.sink)(stock, data) {
+            self.failed = Some(err);
+            return Err(E::custom("sink failed"));
+        }
+        self.count += 1;
+        Ok(())
+    }
+}
+
+impl<'de> Visitor<'de> for &mut Stream<'_> {
+    type Value = ();
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a report")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "stat" => self.stat = Some(map.next_value()?),
+                "tables" => {
+                    self.has_tables = true;
+                    map.next_value_seed(TablesSeed(&mut *self))?;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `tables` of the report
+struct TablesSeed<'s, 'a>(&'s mut Stream<'a>);
+
+impl<'de> DeserializeSeed<'de> for TablesSeed<'_, '_> {
+    type Value = ();
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for TablesSeed<'_, '_> {
+    type Value = ();
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of tables")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while seq.next_element_seed(TableSeed(&mut *self.0))?.is_some() {}
+        Ok(())
+    }
+}
+
+/// One of `tables`, rows of the stock table go to the sink, others are skipped
+struct TableSeed<'s, 'a>(&'s mut Stream<'a>);
+
+impl<'de> DeserializeSeed<'de> for TableSeed<'_, '_> {
+    type Value = ();
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for TableSeed<'_, '_> {
+    type Value = ();
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a table")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let stream = self.0;
+        let mut columns = None;
+        // `data` ahead of `fields` can't be mapped yet
+        let mut pending: Option<Vec<Vec<Value>>> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "fields" => {
+                    let fields: Vec<String> = map.next_value()?;
+                    if !stream.found {
+                        columns = table::columns(&fields, FIELDS);
+                        stream.found = columns.is_some();
+                    }
+                }
+                "data" => match columns {
+                    Some(columns) => map.next_value_seed(RowsSeed(&mut *stream, columns))?,
+                    None if !stream.found => pending = Some(map.next_value()?),
+                    None => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                },
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if let (Some(columns), Some(rows)) = (columns, pending) {
+            for row in rows {
+                stream.emit(&columns, &row)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `data` of the stock table, each row is mapped as soon as it's deserialized
+struct RowsSeed<'s, 'a>(&'s mut Stream<'a>, [usize; FIELDS.len()]);
+
+impl<'de> DeserializeSeed<'de> for RowsSeed<'_, '_> {
+    type Value = ();
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for RowsSeed<'_, '_> {
+    type Value = ();
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of rows")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(row) = seq.next_element::<Vec<Value>>()? {
+            self.0.emit(&self.1, &row)?;
+        }
+        Ok(())
+    }
+}
+
+/// parse the report handing every tradable stock of the day to `sink` as its row is
+/// deserialized, without collecting the table, return the number of stocks
+fn stream_report(body: &[u8], date: NaiveDate, sink: Sink) -> Result<usize, Error> {
+    let mut stream = Stream {
+        date,
+        sink,
+        stat: None,
+        has_tables: false,
+        found: false,
+        count: 0,
+        failed: None,
+    };
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let result = deserializer
+        .deserialize_map(&mut stream)
+        .and_then(|_| deserializer.end());
+    if let Some(err) = stream.failed {
+        return Err(err);
+    }
+    match (result, stream.stat) {
+        (Ok(_), _) if stream.found => Ok(stream.count),
+        // same as `table::parse_json`, a report without tables is a rejected request
+        (_, Some(stat)) if !stream.has_tables => Err(Error::StatMessage(stat)),
+        _ => Err(Error::incompatible()),
+    }
+}
+
+/// parse the report and return every tradable stock of the day
+fn parse_report(body: &[u8], date: NaiveDate) -> Result<Vec<(Stock, DailyData)>, Error> {
+    let mut result = Vec::new();
+    stream_report(body, date, &mut |stock, data| {
+        result.push((stock, data));
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+/// parse the TPEx daily quote and return every traded OTC stock of the day
+fn parse_tpex_report(
+    body: &[u8],
+    date: NaiveDate,
+) -> Result<impl Iterator<Item = (Stock, DailyData)>, Error> {
+    let report: Tables = table::parse_json(body)?;
+    let (table, columns) = report.find(TPEX_FIELDS)?;
+    Ok(table
+        .data
+        .into_iter()
+        .filter_map(move |row| map_tpex(&columns, date, &row)))
+}
+
+fn write_record(
+    writer: &mut impl Write,
+    format: ExportFormat,
+    stock: &Stock,
+    data: &DailyData,
+) -> std::io::Result<()> {
+    match format {
+        ExportFormat::Csv => writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{}",
+            stock.code,
+            data.date,
+            data.volume,
+            data.turnover,
+            data.open_price,
+            data.high_price,
+            data.low_price,
+            data.close_price,
+            data.diff,
+            data.transaction
+        ),
+        ExportFormat::Ndjson => {
+            let record = json!({
+                "code": stock.code,
+                "date": data.date,
+                "volume": data.volume,
+                "turnover": data.turnover,
+                "open_price": data.open_price,
+                "high_price": data.high_price,
+                "low_price": data.low_price,
+                "close_price": data.close_price,
+                "diff": data.diff,
+                "transaction": data.transaction,
+            });
+            serde_json::to_writer(&mut *writer, &record)?;
+            writeln!(writer)
+        }
+    }
+}
+
+static CSV_HEADER: &str =
+    "code,date,volume,turnover,open_price,high_price,low_price,close_price,diff,transaction";
+
+impl History<'_> {
+    /// Fetch the trading summary of every listed stock on a specific day
+    pub async fn fetch_all(&self, date: NaiveDate) -> Result<Vec<(Stock, DailyData)>, Error> {
+        let body = self.fetch_all_raw(date).await?;
+        parse_report(&body, date).map_err(|err| err.with_body(&body))
+    }
+    /// Fetch the trading summary of every stock on `market` on a specific day
+    ///
+    /// Each [`Stock`] is tagged with the `kind` of the exchange it came from.
+    pub async fn fetch_all_in(
+        &self,
+        date: NaiveDate,
+        market: Market,
+    ) -> Result<Vec<(Stock, DailyData)>, Error> {
+        let mut result = Vec::new();
+        if matches!(market, Market::Twse | Market::Both) {
+            result.extend(self.fetch_all(date).await?);
+        }
+        if matches!(market, Market::Tpex | Market::Both) {
+            let body = self.fetch_tpex_raw(date).await?;
+            result.extend(parse_tpex_report(&body, date).map_err(|err| err.with_body(&body))?);
+        }
+        Ok(result)
+    }
+    /// Fetch the trading summary of every listed stock and write each record as it's parsed
+    ///
+    /// Rows are written while the report is deserialized, neither the table nor the records are
+    /// collected. Return the number of records written.
+    pub async fn fetch_all_to_writer(
+        &self,
+        date: NaiveDate,
+        mut writer: impl Write,
+        format: ExportFormat,
+    ) -> Result<usize, Error> {
+        let body = self.fetch_all_raw(date).await?;
+        if format == ExportFormat::Csv {
+            writeln!(writer, "{}", CSV_HEADER)?;
+        }
+        let count = stream_report(&body, date, &mut |stock, data| {
+            Ok(write_record(&mut writer, format, &stock, &data)?)
+        })
+        .map_err(|err| err.with_body(&body))?;
+        writer.flush()?;
+        Ok(count)
+    }
+    /// Fetch the trading summary of every listed stock on every trading day between
+    /// `start` and `end` (inclusive)
+    pub async fn fetch_all_range(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<(Stock, DailyData)>, Error> {
+        self.fetch_all_range_with_progress(start, end, |_, _| {})
+            .await
+    }
+    /// Same as [`History::fetch_all_range`], calling `progress(done, total)` after each day
+    ///
+    /// `total` is the number of weekdays in the range, holidays are counted as done
+    /// without any data.
+    pub async fn fetch_all_range_with_progress(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<(Stock, DailyData)>, Error> {
+        self.fetch_all_range_in(start, end, Market::Twse, progress)
+            .await
+    }
+    /// Fetch the monthly trading history of `stocks` from the whole-market daily report
+    ///
+    /// Each trading day's report is fetched once for all of `stocks`, about 20 requests
+    /// regardless of how many stocks, cheaper than [`History::fetch`] for more than a handful.
+    /// Result is in the same order as `stocks`, a stock without any trade has empty history.
+    pub async fn fetch_month_bulk(
+        &self,
+        year: u16,
+        month: Month,
+        stocks: &[Stock],
+    ) -> Result<Vec<(Stock, Vec<DailyData>)>, Error> {
+        let start = NaiveDate::from_ymd_opt(year as i32, month.number_from_month(), 1)
+            .ok_or(Error::DateDoesNotExist)?;
+        let end = start
+            .checked_add_months(Months::new(1))
+            .and_then(|x| x.pred_opt())
+            .ok_or(Error::DateDoesNotExist)?;
+        let market = match (
+            stocks.iter().any(|x| x.kind == StockKind::Live),
+            stocks.iter().any(|x| x.kind == StockKind::OverTheCounter),
+        ) {
+            (_, false) => Market::Twse,
+            (false, true) => Market::Tpex,
+            (true, true) => Market::Both,
+        };
+        let mut result = stocks
+            .iter()
+            .map(|x| (x.clone(), Vec::new()))
+            .collect::<Vec<_>>();
+        for (stock, data) in self
+            .fetch_all_range_in(start, end, market, |_, _| {})
+            .await?
+        {
+            if let Some((_, history)) = result.iter_mut().find(|x| x.0 == stock) {
+                history.push(data);
+            }
+        }
+        Ok(result)
+    }
+    async fn fetch_all_range_in(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        market: Market,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<(Stock, DailyData)>, Error> {
+        let days = weekdays(start, end);
+        let mut result = Vec::new();
+        for (done, date) in days.iter().enumerate() {
+            match self.fetch_all_in(*date, market).await {
+                Ok(x) => result.extend(x),
+                Err(Error::StatMessage(stat)) if is_no_data(&stat) => {}
+                Err(err) => return Err(err),
+            }
+            progress(done + 1, days.len());
+        }
+        Ok(result)
+    }
+    async fn fetch_all_raw(&self, date: NaiveDate) -> Result<Vec<u8>, Error> {
+        let body = self
+            .0
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &date.format("%Y%m%d").to_string()),
+                    ("type", "ALLBUT0999"),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+
+        Ok(body)
+    }
+    async fn fetch_tpex_raw(&self, date: NaiveDate) -> Result<Vec<u8>, Error> {
+        let body = self
+            .0
+            .get(
+                Family::Report,
+                TPEX_ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &date.format("%Y/%m/%d").to_string()),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 7, 15).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let data = parse_report(include_bytes!("../../test/MI_INDEX.json"), date()).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[1].0.code, 1101);
+        assert_eq!(data[1].1.diff, price("-0.05"));
+        assert_eq!(data[1].1.sign, "-");
+        assert_eq!(data[1].1.direction, PriceDirection::Down);
+        assert_eq!(data[2].1.close_price, price("1010"));
+        assert_eq!(data[2].1.close_price_cents(), 101000);
+        assert_eq!(data[1].1.diff_cents(), -5);
+    }
+    #[test]
+    fn stream() {
+        // `data` ahead of `fields` is mapped once the fields are known
+        let body = r#"{"tables":[{"data":[["2330","1","2","3","4","5","6","7","+","1"]],
+            "fields":["證券代號","成交股數","成交筆數","成交金額","開盤價","最高價","最低價",
+            "收盤價","漲跌(+/-)","漲跌價差"]}],"stat":"OK"}"#;
+        let data = parse_report(body.as_bytes(), date()).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].1.close_price, price("7"));
+        // the sink stops the parse
+        let mut seen = 0;
+        let result = stream_report(
+            include_bytes!("../../test/MI_INDEX.json"),
+            date(),
+            &mut |_, _| {
+                seen += 1;
+                Err(Error::MarketClosed)
+            },
+        );
+        assert!(matches!(result, Err(Error::MarketClosed)));
+        assert_eq!(seen, 1);
+    }
+    #[test]
+    fn parse_tpex() {
+        let data = parse_tpex_report(include_bytes!("../../test/dailyQ.json"), date())
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(data.len(), 3);
+        assert!(data.iter().all(|x| x.0.kind == StockKind::OverTheCounter));
+        assert_eq!(data[0].0.code, 3105);
+        assert_eq!(data[0].1.diff, price("-2.5"));
+        assert_eq!(data[0].1.diff_cents(), -250);
+        assert_eq!(data[1].1.turnover, price("689765432"));
+        assert_eq!(data[2].1.direction, PriceDirection::NotComparable);
+    }
+    #[tokio::test]
+    async fn fetch_all_in() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let history = server.client();
+        let history = history.history();
+        let twse = history.fetch_all_in(date(), Market::Twse).await.unwrap();
+        let tpex = history.fetch_all_in(date(), Market::Tpex).await.unwrap();
+        let both = history.fetch_all_in(date(), Market::Both).await.unwrap();
+        assert_eq!(both.len(), twse.len() + tpex.len());
+        assert_eq!(both[..twse.len()], twse[..]);
+        assert_eq!(both[twse.len()..], tpex[..]);
+    }
+    #[test]
+    fn no_data() {
+        let body = r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#;
+        match parse_report(body.as_bytes(), date()) {
+            Err(Error::StatMessage(stat)) => assert!(is_no_data(&stat)),
+            _ => panic!("expected stat message"),
+        }
+    }
+    #[tokio::test]
+    async fn range_progress() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let mut progress = Vec::new();
+        let data = client
+            .history()
+            .fetch_all_range_with_progress(
+                date(),
+                NaiveDate::from_ymd_opt(2024, 7, 21).unwrap(),
+                |done, total| progress.push((done, total)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 15);
+        assert_eq!(progress, (1..=5).map(|x| (x, 5)).collect::<Vec<_>>());
+        assert_eq!(server.hits("/exchangeReport/MI_INDEX"), 5);
+    }
+    #[tokio::test]
+    async fn fetch_month_bulk() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let stock = |kind, code| Stock { kind, code };
+        let stocks = [
+            stock(StockKind::Live, 2330),
+            stock(StockKind::OverTheCounter, 6488),
+            stock(StockKind::Live, 9999),
+        ];
+        let data = client
+            .history()
+            .fetch_month_bulk(2024, Month::July, &stocks)
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0].0, stocks[0]);
+        // every weekday of July 2024 is served the same fixture
+        assert_eq!(data[0].1.len(), 23);
+        assert_eq!(data[1].1.len(), 23);
+        assert!(data[2].1.is_empty());
+        assert_eq!(server.hits("/exchangeReport/MI_INDEX"), 23);
+        assert_eq!(server.hits("/www/zh-tw/afterTrading/dailyQ"), 23);
+    }
+    #[tokio::test]
+    async fn fetch_all_to_writer() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let mut csv = Vec::new();
+        let count = client
+            .history()
+            .fetch_all_to_writer(date(), &mut csv, ExportFormat::Csv)
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().next(), Some(CSV_HEADER));
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().last().unwrap().starts_with("2330,2024-07-15,"));
+    }
+    #[test]
+    fn write() {
+        let mut csv = Vec::new();
+        for (stock, data) in
+            parse_report(include_bytes!("../../test/MI_INDEX.json"), date()).unwrap()
+        {
+            write_record(&mut csv, ExportFormat::Csv, &stock, &data).unwrap();
+            write_record(&mut csv, ExportFormat::Ndjson, &stock, &data).unwrap();
+        }
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        #[cfg(not(feature = "decimal"))]
+        let expected = "2330,2024-07-15,35123456,35500123456,1005,1015,1000,1010,25,60123";
+        // decimal keeps the scale of upstream text
+        #[cfg(feature = "decimal")]
+        let expected =
+            "2330,2024-07-15,35123456,35500123456,1005.00,1015.00,1000.00,1010.00,25.00,60123";
+        assert_eq!(lines[4], expected);
+        let record: Value = serde_json::from_str(lines[5]).unwrap();
+        assert_eq!(record["code"], 2330);
+        assert_eq!(record["date"], "2024-07-15");
+    }
+}
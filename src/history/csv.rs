@@ -0,0 +1,104 @@
+//! CSV form of `STOCK_DAY` (`response=csv`), a fallback when the JSON schema breaks
+
+use serde_json::Value;
+
+use super::{RawMonthData, FIELDS, FIELD_COUNT};
+use crate::{list::parser::big5_to_utf8, Error};
+
+/// `stat` reported for an empty CSV, which is how upstream answers a query without data
+const NO_DATA_STAT: &str = "很抱歉，沒有符合條件的資料!";
+
+/// split a CSV line into cells, `""` in a quoted cell is an escaped quote
+fn split_line(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    // every row of upstream ends with a comma
+    if cells.last().is_some_and(String::is_empty) {
+        cells.pop();
+    }
+    cells
+}
+
+/// parse the Big5 encoded CSV into the same raw form as JSON
+///
+/// Header row is located by its first column, rows after it until the first row of other
+/// width are data, and the rest are notes.
+pub(crate) fn parse_csv(body: &[u8]) -> Result<RawMonthData, Error> {
+    let content = big5_to_utf8(body.to_vec());
+    if content.trim().is_empty() {
+        return Err(Error::StatMessage(NO_DATA_STAT.to_string()));
+    }
+    let mut rows = content
+        .lines()
+        .map(split_line)
+        .filter(|x| !x.is_empty())
+        .skip_while(|x| x.len() != FIELD_COUNT || x[0].trim() != FIELDS[0]);
+    let fields = rows
+        .next()
+        .and_then(|x| x.try_into().ok())
+        .ok_or(Error::incompatible())?;
+
+    let mut data = Vec::new();
+    let mut rest = Vec::new();
+    for row in rows {
+        match <[String; FIELD_COUNT]>::try_from(row) {
+            Ok(row) if rest.is_empty() => data.push(row.map(Value::String)),
+            Ok(row) => rest.extend(row),
+            Err(row) => rest.extend(row),
+        }
+    }
+    let notes = rest
+        .into_iter()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty() && x != "說明:")
+        .collect();
+    Ok(RawMonthData {
+        stat: String::new(),
+        fields,
+        data,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        history::{parse_month, parse_raw, DuplicateDate},
+        ParseMode,
+    };
+
+    #[test]
+    fn split() {
+        assert_eq!(split_line(r#""a","1,234","#), ["a", "1,234"]);
+        assert_eq!(split_line(r#""a","","b""#), ["a", "", "b"]);
+        assert_eq!(split_line(r#""say ""hi""",x"#), [r#"say "hi""#, "x"]);
+    }
+    #[test]
+    fn same_as_json() {
+        let parse = |raw| parse_month(raw, DuplicateDate::Reject, ParseMode::Strict).unwrap();
+        let csv = parse_csv(include_bytes!("../../test/STOCK_DAY.csv")).unwrap();
+        assert_eq!(csv.notes.len(), 3);
+        let json = parse_raw(include_bytes!("../../test/STOCK_DAY.json")).unwrap();
+        assert_eq!(parse(csv), parse(json));
+        assert!(matches!(parse_csv(b"\r\n"), Err(Error::StatMessage(_))));
+        assert!(matches!(
+            parse_csv(b"\"a\",\"b\",\r\n"),
+            Err(Error::IncompatibleApi { .. })
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+//! TWSE daily category indices (`MI_INDEX` price index tables)
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{bulk, History, PriceDirection};
+use crate::table::{self, parse_cell, Tables};
+use crate::{list::Industry, Error, Family, MediaType};
+
+/// column headers of the `MI_INDEX` price index table this module reads
+pub(crate) const FIELDS: [&str; 5] = ["指數", "收盤指數", "漲跌(+/-)", "漲跌點數", "漲跌百分比(%)"];
+
+/// Name of each category index without the `類指數` suffix, and its industry
+const INDUSTRIES: [(&str, Industry); 34] = [
+    ("水泥", Industry::Cement),
+    ("食品", Industry::Food),
+    ("塑膠", Industry::Plastics),
+    ("紡織纖維", Industry::Textiles),
+    ("電機機械", Industry::Motor),
+    ("電器電纜", Industry::ElectricCable),
+    ("化學生技醫療", Industry::ChemicalBiotech),
+    ("化學", Industry::Chemical),
+    ("生技醫療", Industry::Biotech),
+    ("玻璃陶瓷", Industry::Glass),
+    ("造紙", Industry::Paper),
+    ("鋼鐵", Industry::Steel),
+    ("橡膠", Industry::Rubber),
+    ("汽車", Industry::Car),
+    ("半導體", Industry::Semiconductor),
+    ("電腦及週邊設備", Industry::Computer),
+    ("光電", Industry::Optoelectronic),
+    ("通信網路", Industry::Communications),
+    ("電子零組件", Industry::Electronic),
+    ("電子通路", Industry::ElectronicDistribution),
+    ("資訊服務", Industry::InformationService),
+    ("其他電子", Industry::OtherElectronic),
+    ("建材營造", Industry::Construction),
+    ("航運", Industry::Shipping),
+    ("觀光", Industry::Tourism),
+    ("觀光餐旅", Industry::TourismHospitality),
+    ("金融保險", Industry::Financial),
+    ("貿易百貨", Industry::Trading),
+    ("油電燃氣", Industry::OilGasElectricity),
+    ("綠能環保", Industry::GreenEnergy),
+    ("數位雲端", Industry::DigitalCloud),
+    ("運動休閒", Industry::SportsLeisure),
+    ("居家生活", Industry::Household),
+    ("其他", Industry::Others),
+];
+
+/// Closing value of a TWSE index on a day
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CategoryIndex {
+    /// Be noted that `date` is `UTC+8`
+    pub date: NaiveDate,
+    /// chinese name as TWSE displays it, e.g. `半導體類指數`
+    pub name: String,
+    /// industry the index tracks, `None` for market-wide or themed index (e.g. TAIEX, `電子類指數`)
+    pub industry: Option<Industry>,
+    /// closing value
+    pub close: f64,
+    /// change from previous day, negative if down
+    pub diff: f64,
+    /// change from previous day in percent
+    pub change_percent: f64,
+}
+
+/// industry tracked by an index named like `半導體類指數`
+fn industry_of(name: &str) -> Option<Industry> {
+    let name = name.trim().strip_suffix("類指數")?;
+    INDUSTRIES
+        .iter()
+        .find(|(x, _)| *x == name)
+        .map(|(_, industry)| industry.clone())
+}
+
+/// Position of each column in a price index table
+struct FieldMapper([usize; FIELDS.len()]);
+
+impl FieldMapper {
+    fn new(fields: &[String]) -> Option<Self> {
+        table::columns(fields, FIELDS).map(Self)
+    }
+    fn map(&self, date: NaiveDate, row: &[Value]) -> Option<CategoryIndex> {
+        let get = |i: usize| row.get(self.0[i]);
+        let name = get(0)?.as_str()?.trim().to_string();
+        let sign = bulk::strip_tags(get(2)?.as_str()?);
+        let diff: f64 = parse_cell(get(3)?)?;
+        let diff = match PriceDirection::from_token(&sign) {
+            PriceDirection::Down => -diff.abs(),
+            _ => diff,
+        };
+        Some(CategoryIndex {
+            date,
+            industry: industry_of(&name),
+            name,
+            close: parse_cell(get(1)?)?,
+            diff,
+            change_percent: parse_cell(get(4)?)?,
+        })
+    }
+}
+
+/// parse every price index table of the report, return indices are skipped
+fn parse_report(body: &[u8], date: NaiveDate) -> Result<Vec<CategoryIndex>, Error> {
+    let report: Tables = table::parse_json(body)?;
+    let mut found = false;
+    let mut result = Vec::new();
+    for table in report.tables {
+        if table.title.contains("報酬指數") {
+            continue;
+        }
+        let Some(mapper) = FieldMapper::new(&table.fields) else {
+            continue;
+        };
+        found = true;
+        for row in table.data.iter() {
+            result.push(mapper.map(date, row).ok_or(Error::incompatible())?);
+        }
+    }
+    match found {
+        true => Ok(result),
+        false => Err(Error::incompatible()),
+    }
+}
+
+impl History<'_> {
+    /// Fetch the closing value of every TWSE price index on a specific day, including
+    /// category (sector) indices like `半導體類指數`
+    pub async fn fetch_category_indices(
+        &self,
+        date: NaiveDate,
+    ) -> Result<Vec<CategoryIndex>, Error> {
+        let body = self
+            .0
+            .get(
+                Family::Report,
+                bulk::ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &date.format("%Y%m%d").to_string()),
+                    ("type", "IND"),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+        parse_report(&body, date).map_err(|err| err.with_body(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 7, 15).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let data = parse_report(include_bytes!("../../test/MI_INDEX.json"), date()).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[1].name, "發行量加權股價指數");
+        assert_eq!(data[1].close, 23916.93);
+        assert_eq!(data[1].industry, None);
+        assert_eq!(data[2].diff, -3.21);
+        assert_eq!(data[2].change_percent, -0.24);
+        assert!(matches!(
+            parse_report(br#"{"stat":"OK","tables":[]}"#, date()),
+            Err(Error::IncompatibleApi { .. })
+        ));
+    }
+    #[test]
+    fn industry() {
+        assert_eq!(industry_of("半導體類指數"), Some(Industry::Semiconductor));
+        assert_eq!(industry_of("金融保險類指數"), Some(Industry::Financial));
+        assert_eq!(industry_of("電子類指數"), None);
+        assert_eq!(industry_of("發行量加權股價指數"), None);
+        for (name, industry) in INDUSTRIES {
+            assert!(industry.is_known(), "{}", name);
+        }
+    }
+    #[tokio::test]
+    async fn fetch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let data = server
+            .client()
+            .history()
+            .fetch_category_indices(date())
+            .await
+            .unwrap();
+        assert_eq!(data[0].name, "寶島股價指數");
+    }
+}
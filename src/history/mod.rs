@@ -0,0 +1,962 @@
+//! TWSE monthly trading history data API
+
+pub(crate) mod bulk;
+mod csv;
+pub(crate) mod index;
+pub(crate) mod summary;
+pub(crate) mod tpex;
+
+pub use bulk::{ExportFormat, Market};
+pub use index::CategoryIndex;
+pub use summary::MonthlySummary;
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Month, Months, NaiveDate};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    is_no_data, is_unknown_stock, parse_roc_date, price_from, price_to_cents, strip_csv_guard,
+    Client, Error, Family, FromJsonNumber, ParseMode, Price, ReportFormat, Stock, StockKind,
+};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/exchangeReport/STOCK_DAY";
+/// requests in flight of [`History::fetch_batch`]
+pub const BATCH_CONCURRENCY: usize = 4;
+const FIELD_COUNT: usize = 9;
+/// column headers of `STOCK_DAY` this module understands
+pub(crate) const FIELDS: [&str; FIELD_COUNT] = [
+    "日期",
+    "成交股數",
+    "成交金額",
+    "開盤價",
+    "最高價",
+    "最低價",
+    "收盤價",
+    "漲跌價差",
+    "成交筆數",
+];
+
+/// Trading summary of a single day
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DailyData {
+    /// Be noted that `date` is `UTC+8`
+    pub date: NaiveDate,
+    /// traded shares
+    pub volume: u64,
+    /// total traded value (成交金額) in NT dollars, not a per-share price
+    #[cfg_attr(feature = "serde", serde(alias = "transaction_price"))]
+    pub turnover: Price,
+    /// opening price
+    pub open_price: Price,
+    /// highest price of the day
+    pub high_price: Price,
+    /// lowest price of the day
+    pub low_price: Price,
+    /// closing price
+    pub close_price: Price,
+    /// today close price - today start price
+    pub diff: Price,
+    /// number of matched trades (成交筆數)
+    pub transaction: u64,
+    /// direction TWSE indicated for `diff`
+    pub direction: PriceDirection,
+    /// raw up/down token TWSE sent (`+`, `-`, `X` or empty), kept for auditing
+    pub sign: String,
+}
+
+/// Up/down indicator TWSE attaches to a day
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PriceDirection {
+    Up,
+    Down,
+    #[default]
+    Flat,
+    /// `X`, not comparable to previous day (e.g. ex-dividend, first trading day)
+    NotComparable,
+}
+
+/// Scale of the `*_cents` accessors of [`DailyData`], prices are in hundredths of NT dollar
+///
+/// Rounding recovers the quoted value exactly, as TWSE quotes at most 2 decimals.
+pub const PRICE_SCALE: i64 = 100;
+
+impl DailyData {
+    /// `open_price` as an integer of [`PRICE_SCALE`]
+    pub fn open_price_cents(&self) -> i64 {
+        price_to_cents(self.open_price)
+    }
+    /// `high_price` as an integer of [`PRICE_SCALE`]
+    pub fn high_price_cents(&self) -> i64 {
+        price_to_cents(self.high_price)
+    }
+    /// `low_price` as an integer of [`PRICE_SCALE`]
+    pub fn low_price_cents(&self) -> i64 {
+        price_to_cents(self.low_price)
+    }
+    /// `close_price` as an integer of [`PRICE_SCALE`]
+    pub fn close_price_cents(&self) -> i64 {
+        price_to_cents(self.close_price)
+    }
+    /// `diff` as an integer of [`PRICE_SCALE`], negative if down
+    pub fn diff_cents(&self) -> i64 {
+        price_to_cents(self.diff)
+    }
+    /// Volume weighted average price, `None` if nothing was traded
+    pub fn vwap(&self) -> Option<Price> {
+        match self.volume {
+            0 => None,
+            volume => Some(self.turnover / price_from(volume)),
+        }
+    }
+}
+
+impl PriceDirection {
+    pub(crate) fn from_token(token: &str) -> Self {
+        match token.trim() {
+            "+" => PriceDirection::Up,
+            "-" => PriceDirection::Down,
+            "X" | "x" => PriceDirection::NotComparable,
+            _ => PriceDirection::Flat,
+        }
+    }
+}
+
+enum Column {
+    Date,
+    Volume,
+    Turnover,
+    OpenPrice,
+    HighPrice,
+    LowPrice,
+    ClosePrice,
+    Diff,
+    Transaction,
+}
+
+struct FieldMapper([Column; FIELD_COUNT]);
+
+impl FieldMapper {
+    fn new<'a>(fields: impl Iterator<Item = &'a str>) -> Result<Self, Error> {
+        let fields: Vec<_> = fields.collect();
+        let map = fields
+            .iter()
+            .flat_map(|field| match *field {
+                "日期" => Ok(Column::Date),
+                "成交股數" => Ok(Column::Volume),
+                "成交金額" => Ok(Column::Turnover),
+                "開盤價" => Ok(Column::OpenPrice),
+                "最高價" => Ok(Column::HighPrice),
+                "最低價" => Ok(Column::LowPrice),
+                "收盤價" => Ok(Column::ClosePrice),
+                "漲跌價差" => Ok(Column::Diff),
+                "成交筆數" => Ok(Column::Transaction),
+                _ => Err(Error::incompatible()),
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| Error::IncompatibleApi {
+                context: format!("fields {:?}", fields),
+            })?;
+        Ok(Self(map))
+    }
+    fn map(&self, data: &[Value; FIELD_COUNT]) -> Result<DailyData, Error> {
+        let mut result = DailyData::default();
+        macro_rules! parse {
+            ($f:ident,$v:expr,$t:ty) => {
+                result.$f = match $v {
+                    Value::Number(x) => <$t>::from_json_number(x),
+                    Value::String(x) => strip_csv_guard(x).replace(",", "").parse().ok(),
+                    _ => return Err(Error::incompatible()),
+                }
+                .ok_or(Error::incompatible())?
+            };
+        }
+        for (value, key) in data.iter().zip(self.0.iter()) {
+            match key {
+                Column::Date => {
+                    result.date = value
+                        .as_str()
+                        .and_then(parse_roc_date)
+                        .ok_or(Error::incompatible())?
+                }
+                Column::Volume => parse!(volume, value, u64),
+                Column::Turnover => parse!(turnover, value, Price),
+                Column::OpenPrice => parse!(open_price, value, Price),
+                Column::HighPrice => parse!(high_price, value, Price),
+                Column::LowPrice => parse!(low_price, value, Price),
+                Column::ClosePrice => parse!(close_price, value, Price),
+                Column::Diff => {
+                    let raw = match value {
+                        Value::Number(x) => x.to_string(),
+                        Value::String(x) => strip_csv_guard(x).to_string(),
+                        _ => return Err(Error::incompatible()),
+                    };
+                    (result.sign, result.direction, result.diff) =
+                        parse_signed(&raw).ok_or(Error::incompatible())?;
+                }
+                Column::Transaction => parse!(transaction, value, u64),
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// split a signed cell like `+6.00`, `-3.00` or `X0.00` into sign token, direction and diff
+pub(crate) fn parse_signed(raw: &str) -> Option<(String, PriceDirection, Price)> {
+    let raw = raw.trim().replace(',', "");
+    let at = raw
+        .find(|c: char| c.is_ascii_digit() || c == '.')
+        .unwrap_or(raw.len());
+    let (sign, diff) = raw.split_at(at);
+    let diff: Price = diff.parse().ok()?;
+    let direction = PriceDirection::from_token(sign);
+    let diff = match direction {
+        PriceDirection::Down => -diff,
+        _ => diff,
+    };
+    Some((sign.to_string(), direction, diff))
+}
+
+/// Upstream metadata of a successful response
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResponseMeta {
+    /// raw `stat` upstream sent, `OK` unless it carries a warning (e.g. delayed or partial data)
+    ///
+    /// Empty if the format carries none, i.e. [`ReportFormat::Csv`].
+    pub stat: String,
+}
+
+impl ResponseMeta {
+    /// Whether `stat` is anything other than a plain `OK`
+    pub fn has_warning(&self) -> bool {
+        !self.stat.is_empty() && !self.stat.trim().eq_ignore_ascii_case("ok")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawMonthData {
+    #[serde(default)]
+    stat: String,
+    fields: [String; FIELD_COUNT],
+    data: Vec<[Value; FIELD_COUNT]>,
+    #[serde(default)]
+    notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawErrorMessage {
+    stat: String,
+}
+
+/// How to handle rows sharing the same date in a single month
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateDate {
+    /// keep the last row of each date
+    #[default]
+    KeepLast,
+    /// return [`Error::IncompatibleApi`] naming the duplicated date
+    Reject,
+}
+
+/// newtype wrapper for the [`Client`] facilitating realtime data fetching
+pub struct History<'a>(&'a Client, DuplicateDate);
+
+impl Client {
+    /// Get the history API client
+    pub fn history(&self) -> History<'_> {
+        History(self, DuplicateDate::default())
+    }
+}
+
+impl History<'_> {
+    /// Set how rows sharing the same date are handled
+    pub fn on_duplicate(mut self, policy: DuplicateDate) -> Self {
+        self.1 = policy;
+        self
+    }
+    /// Fetch the trading history of a stock in a specific month
+    ///
+    /// return every day that market open in the month
+    ///
+    /// OTC stock ([`StockKind::OverTheCounter`]) is fetched from TPEx instead of TWSE, with
+    /// volume and turnover converted to shares and NT dollars.
+    ///
+    /// Delisted stocks are not in [`crate::list`] but their history is still available:
+    /// ```rust,no_run
+    /// use chrono::Month;
+    /// use twstock::*;
+    ///
+    /// async fn fetch() {
+    ///     let client = Client::new();
+    ///     // 日月光 (2311), delisted on 2018/04/30
+    ///     let data = client
+    ///         .history()
+    ///         .fetch(
+    ///             Month::April,
+    ///             2018,
+    ///             Stock {
+    ///                 kind: StockKind::Live,
+    ///                 code: 2311,
+    ///             },
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(!data.is_empty());
+    /// }
+    /// ```
+    pub async fn fetch(
+        &self,
+        month: Month,
+        year: u16,
+        stock: Stock,
+    ) -> Result<Vec<DailyData>, Error> {
+        if stock.kind == StockKind::OverTheCounter {
+            return Ok(self.fetch_tpex(month, year, stock).await?.0);
+        }
+        let (data, body) = self.fetch_raw(month, year, stock).await?;
+        parse_month(data, self.1, self.0.parse_mode).map_err(|err| err.with_body(&body))
+    }
+    /// Same as [`History::fetch`], together with the `stat` upstream sent along the data
+    ///
+    /// OTC stock is read from TPEx the same way as [`History::fetch`].
+    pub async fn fetch_with_meta(
+        &self,
+        month: Month,
+        year: u16,
+        stock: Stock,
+    ) -> Result<(Vec<DailyData>, ResponseMeta), Error> {
+        if stock.kind == StockKind::OverTheCounter {
+            return self.fetch_tpex(month, year, stock).await;
+        }
+        let (mut data, body) = self.fetch_raw(month, year, stock).await?;
+        let meta = ResponseMeta {
+            stat: std::mem::take(&mut data.stat),
+        };
+        let data =
+            parse_month(data, self.1, self.0.parse_mode).map_err(|err| err.with_body(&body))?;
+        Ok((data, meta))
+    }
+    /// Same as [`History::fetch`], keyed by date for lookup and range query
+    ///
+    /// Duplicated date is resolved by [`History::on_duplicate`] before collecting.
+    pub async fn fetch_map(
+        &self,
+        month: Month,
+        year: u16,
+        stock: Stock,
+    ) -> Result<BTreeMap<NaiveDate, DailyData>, Error> {
+        Ok(self
+            .fetch(month, year, stock)
+            .await?
+            .into_iter()
+            .map(|x| (x.date, x))
+            .collect())
+    }
+    /// Fetch the trading history of a stock on a single day, `None` if it wasn't a trading
+    /// day of the stock
+    ///
+    /// The whole month containing `date` is requested, prefer [`History::fetch`] or
+    /// [`History::fetch_range`] for many days.
+    pub async fn fetch_day(
+        &self,
+        date: NaiveDate,
+        stock: Stock,
+    ) -> Result<Option<DailyData>, Error> {
+        let month = Month::try_from(date.month() as u8).map_err(|_| Error::DateDoesNotExist)?;
+        match self.fetch(month, date.year() as u16, stock).await {
+            Ok(data) => Ok(data.into_iter().find(|x| x.date == date)),
+            Err(Error::StatMessage(stat)) if is_no_data(&stat) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+    /// Fetch the trading history of many `(month, year, stock)` concurrently
+    ///
+    /// At most [`BATCH_CONCURRENCY`] requests are in flight, sharing pooled connections of
+    /// the [`Client`] (multiplexed on a single connection if upstream speaks HTTP/2).
+    /// Result is in the same order as `requests`.
+    pub async fn fetch_batch(
+        &self,
+        requests: impl IntoIterator<Item = (Month, u16, Stock)>,
+    ) -> Result<Vec<Vec<DailyData>>, Error> {
+        stream::iter(requests)
+            .map(|(month, year, stock)| self.fetch(month, year, stock))
+            .buffered(BATCH_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+    /// Fetch the trading history of a stock on every trading day between `start` and `end`
+    /// (inclusive), one request per month spanned by the range
+    ///
+    /// A month without data (e.g. before listing) is skipped. Result is sorted by date
+    /// without duplicated day.
+    pub async fn fetch_range(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        stock: Stock,
+    ) -> Result<Vec<DailyData>, Error> {
+        let mut months = Vec::new();
+        let mut first = start.with_day(1).ok_or(Error::DateDoesNotExist)?;
+        while first <= end {
+            let month =
+                Month::try_from(first.month() as u8).map_err(|_| Error::DateDoesNotExist)?;
+            months.push((month, first.year() as u16));
+            first = first
+                .checked_add_months(Months::new(1))
+                .ok_or(Error::DateDoesNotExist)?;
+        }
+        let mut result = self
+            .fetch_months_concurrent(months, stock, BATCH_CONCURRENCY)
+            .await?;
+        result.retain(|x| (start..=end).contains(&x.date));
+        Ok(result)
+    }
+    /// Fetch the trading history of a stock in each of `months`, at most `concurrency`
+    /// requests in flight
+    ///
+    /// A month without data (e.g. before listing) is skipped. Result is sorted by date
+    /// without duplicated day, whatever order the requests complete in.
+    pub async fn fetch_months_concurrent(
+        &self,
+        months: Vec<(Month, u16)>,
+        stock: Stock,
+        concurrency: usize,
+    ) -> Result<Vec<DailyData>, Error> {
+        let mut months = stream::iter(months)
+            .map(|(month, year)| self.fetch(month, year, stock.clone()))
+            .buffered(concurrency.max(1));
+
+        let mut result = Vec::new();
+        while let Some(data) = months.next().await {
+            match data {
+                Ok(data) => result.extend(data),
+                Err(Error::StatMessage(stat)) if is_no_data(&stat) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        result.sort_by_key(|x| x.date);
+        result.dedup_by_key(|x| x.date);
+        Ok(result)
+    }
+    /// parsed month together with the body it was read from
+    async fn fetch_raw(
+        &self,
+        month: Month,
+        year: u16,
+        stock: Stock,
+    ) -> Result<(RawMonthData, Vec<u8>), Error> {
+        stock.validate()?;
+        let date = NaiveDate::from_ymd_opt(year as i32, month.number_from_month(), 1)
+            .ok_or(Error::DateDoesNotExist)?
+            .format("%Y%m%d")
+            .to_string();
+
+        let format = self.0.report_format;
+        let body = self
+            .0
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[
+                    ("response", format.as_query()),
+                    ("date", &date),
+                    ("stockNo", &stock.code.to_string()),
+                ],
+                format.media_type(),
+            )
+            .await?;
+        let raw = match format {
+            ReportFormat::Json => parse_raw(&body),
+            ReportFormat::Csv => csv::parse_csv(&body),
+        };
+        let raw = raw.map_err(|err| match err {
+            Error::StatMessage(stat) if is_unknown_stock(&stat) => Error::UnknownStock(stock.code),
+            err => err.with_body(&body),
+        })?;
+        Ok((raw, body))
+    }
+}
+
+fn parse_raw(body: &[u8]) -> Result<RawMonthData, Error> {
+    match serde_json::from_slice(body) {
+        Ok(x) => Ok(x),
+        Err(_) => {
+            let x: RawErrorMessage =
+                serde_json::from_slice(body).map_err(|_| Error::incompatible())?;
+            Err(Error::StatMessage(x.stat))
+        }
+    }
+}
+
+fn parse_month(
+    data: RawMonthData,
+    policy: DuplicateDate,
+    mode: ParseMode,
+) -> Result<Vec<DailyData>, Error> {
+    let mapper = FieldMapper::new(data.fields.iter().map(|s| s.as_str()))?;
+    let mut result: Vec<DailyData> = Vec::with_capacity(data.data.len());
+    for row in data.data.iter() {
+        let daily = match mapper.map(row) {
+            Ok(x) => x,
+            Err(_) if mode == ParseMode::Lenient => continue,
+            Err(err) => return Err(err),
+        };
+        push_daily(&mut result, daily, policy)?;
+    }
+    Ok(result)
+}
+
+/// append `daily` to `result`, resolving a duplicated date by `policy`
+fn push_daily(
+    result: &mut Vec<DailyData>,
+    daily: DailyData,
+    policy: DuplicateDate,
+) -> Result<(), Error> {
+    match result.iter().position(|x| x.date == daily.date) {
+        Some(_) if policy == DuplicateDate::Reject => {
+            return Err(Error::IncompatibleApi {
+                context: format!("duplicated date {}", daily.date),
+            })
+        }
+        Some(i) => result[i] = daily,
+        None => result.push(daily),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Datelike;
+
+    use super::*;
+    use crate::{price, Stock, StockKind};
+
+    #[tokio::test]
+    async fn fetch() {
+        let client = Client::new();
+        let data = client
+            .history()
+            .fetch(
+                Month::January,
+                2021,
+                Stock {
+                    kind: StockKind::Live,
+                    code: 2330,
+                },
+            )
+            .await
+            .unwrap();
+        for item in &data {
+            assert_eq!(item.date.year(), 2021)
+        }
+        assert_eq!(data.len(), 20);
+    }
+    #[test]
+    fn cents() {
+        let data = DailyData {
+            open_price: price("536.01"),
+            high_price: price("0.29"),
+            low_price: price("9.95"),
+            close_price: price("1005.00"),
+            diff: -price("1.15"),
+            ..Default::default()
+        };
+        assert_eq!(data.open_price_cents(), 53601);
+        assert_eq!(data.high_price_cents(), 29);
+        assert_eq!(data.low_price_cents(), 995);
+        assert_eq!(data.close_price_cents(), 100500);
+        assert_eq!(data.diff_cents(), -115);
+        // STOCK_DAY cells
+        let data = parse_month(
+            parse_raw(include_bytes!("../../test/STOCK_DAY.json")).unwrap(),
+            DuplicateDate::Reject,
+            ParseMode::Strict,
+        )
+        .unwrap();
+        assert_eq!(data[0].open_price_cents(), 53000);
+        assert_eq!(data[0].high_price_cents(), 54000);
+        assert_eq!(data[0].low_price_cents(), 52800);
+        assert_eq!(data[0].close_price_cents(), 53600);
+        assert_eq!(data[0].diff_cents(), 600);
+        // thousands separator
+        let body = String::from_utf8_lossy(include_bytes!("../../test/STOCK_DAY.json"))
+            .replace(r#""536.00""#, r#""1,005.29""#);
+        let data = parse_month(
+            parse_raw(body.as_bytes()).unwrap(),
+            DuplicateDate::Reject,
+            ParseMode::Strict,
+        )
+        .unwrap();
+        assert_eq!(data[0].close_price_cents(), 100529);
+    }
+    #[tokio::test]
+    async fn fetch_with_meta() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let (data, meta) = server
+            .client()
+            .history()
+            .fetch_with_meta(Month::January, 2021, stock.clone())
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(meta.stat, "OK");
+        assert!(!meta.has_warning());
+        let delayed = String::from_utf8_lossy(include_bytes!("../../test/STOCK_DAY.json"))
+            .replace(r#""stat":"OK""#, r#""stat":"資料延遲""#);
+        server.respond("/exchangeReport/STOCK_DAY", 200, delayed);
+        let (data, meta) = server
+            .client()
+            .history()
+            .fetch_with_meta(Month::January, 2021, stock)
+            .await
+            .unwrap();
+        assert!(!data.is_empty());
+        assert!(meta.has_warning());
+    }
+    #[tokio::test]
+    async fn delisted() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/exchangeReport/STOCK_DAY",
+            200,
+            &include_bytes!("../../test/STOCK_DAY.delisted.json")[..],
+        );
+        let data = server
+            .client()
+            .history()
+            .fetch(
+                Month::March,
+                2018,
+                Stock {
+                    kind: StockKind::Live,
+                    code: 2311,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[2].close_price, price("40.4"));
+        assert_eq!(server.hits("/exchangeReport/STOCK_DAY"), 1);
+    }
+    #[tokio::test]
+    async fn raw() {
+        let client = Client::new();
+        let data = client
+            .history()
+            .fetch_raw(
+                Month::January,
+                2021,
+                Stock {
+                    kind: StockKind::Live,
+                    code: 2330,
+                },
+            )
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(data.data.len(), 20);
+    }
+    #[tokio::test]
+    async fn fetch_mock() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let data = server
+            .client()
+            .history()
+            .fetch(
+                Month::January,
+                2021,
+                Stock {
+                    kind: StockKind::Live,
+                    code: 2330,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(data[0].close_price > price("0"));
+        assert!(data[0].low_price <= data[0].high_price);
+    }
+    #[tokio::test]
+    async fn row_error_body() {
+        let server = crate::mock::MockServer::start();
+        let body = String::from_utf8_lossy(include_bytes!("../../test/STOCK_DAY.json"))
+            .replace(r#""536.00""#, r#""abc""#);
+        server.respond("/exchangeReport/STOCK_DAY", 200, body);
+        let client = server.client();
+        let history = client.history();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let err = history
+            .fetch(Month::January, 2021, stock.clone())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::IncompatibleApi { context } if context.starts_with(r#"{"stat""#))
+        );
+        let err = history
+            .fetch_with_meta(Month::January, 2021, stock)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::IncompatibleApi { context } if !context.is_empty()));
+    }
+    #[tokio::test]
+    async fn unknown_stock() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/exchangeReport/STOCK_DAY",
+            200,
+            &include_bytes!("../../test/STOCK_DAY.unknown.json")[..],
+        );
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 9999,
+        };
+        let err = server
+            .client()
+            .history()
+            .fetch(Month::January, 2021, stock.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::UnknownStock(x) if x == stock.code));
+        // rejected before sending
+        let err = server
+            .client()
+            .history()
+            .fetch(Month::January, 2021, Stock::live(99_999_999))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidStockCode(99_999_999)));
+        assert_eq!(server.hits("/exchangeReport/STOCK_DAY"), 1);
+        // no data of a known stock stays as is
+        server.respond(
+            "/exchangeReport/STOCK_DAY",
+            200,
+            r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#,
+        );
+        let err = server
+            .client()
+            .history()
+            .fetch(Month::January, 2021, stock)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::StatMessage(_)));
+    }
+    #[tokio::test]
+    async fn fetch_range() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        // mock serves the same month of 2021/01 for both requests
+        let data = server
+            .client()
+            .history()
+            .fetch_range(
+                NaiveDate::from_ymd_opt(2020, 12, 15).unwrap(),
+                NaiveDate::from_ymd_opt(2021, 1, 6).unwrap(),
+                stock,
+            )
+            .await
+            .unwrap();
+        assert_eq!(server.hits("/exchangeReport/STOCK_DAY"), 2);
+        assert_eq!(
+            data.iter().map(|x| x.date.day()).collect::<Vec<_>>(),
+            [4, 5, 6]
+        );
+    }
+    #[tokio::test]
+    async fn fetch_months_concurrent() {
+        let server = crate::mock::MockServer::start();
+        let body = |x: &str| include_str!("../../test/STOCK_DAY.json").replace("110/01/", x);
+        // the later month is served to whichever request arrives first
+        server.enqueue("/exchangeReport/STOCK_DAY", 200, body("110/03/"));
+        server.enqueue("/exchangeReport/STOCK_DAY", 200, body("110/02/"));
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let data = server
+            .client()
+            .history()
+            .fetch_months_concurrent(
+                vec![(Month::February, 2021), (Month::March, 2021)],
+                stock,
+                2,
+            )
+            .await
+            .unwrap();
+        assert_eq!(data.first().unwrap().date.month(), 2);
+        assert_eq!(data.last().unwrap().date.month(), 3);
+        assert!(data.windows(2).all(|x| x[0].date < x[1].date));
+    }
+    #[tokio::test]
+    async fn fetch_csv() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/exchangeReport/STOCK_DAY",
+            200,
+            &include_bytes!("../../test/STOCK_DAY.csv")[..],
+        );
+        let client = server.client().with_report_format(ReportFormat::Csv);
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let data = client
+            .history()
+            .fetch(Month::January, 2021, stock)
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(data[4].close_price, price("580"));
+    }
+    #[tokio::test]
+    async fn fetch_map() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let data = server
+            .client()
+            .history()
+            .fetch_map(Month::January, 2021, stock)
+            .await
+            .unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2021, 1, day).unwrap();
+        assert_eq!(data[&date(5)].close_price, price("542"));
+        assert_eq!(data.range(date(6)..).count(), 3);
+    }
+    #[tokio::test]
+    async fn fetch_day() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let date = |day| NaiveDate::from_ymd_opt(2021, 1, day).unwrap();
+        let data = client
+            .history()
+            .fetch_day(date(5), stock.clone())
+            .await
+            .unwrap();
+        assert_eq!(data.unwrap().close_price, price("542"));
+        // weekend
+        let data = client.history().fetch_day(date(9), stock).await.unwrap();
+        assert_eq!(data, None);
+    }
+    #[tokio::test]
+    async fn fetch_batch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let requests = (1..=12).map(|x| (Month::try_from(x).unwrap(), 2021, stock.clone()));
+        let data = client.history().fetch_batch(requests).await.unwrap();
+        assert_eq!(data.len(), 12);
+        assert_eq!(server.hits("/exchangeReport/STOCK_DAY"), 12);
+        // connections are pooled instead of one per request
+        assert!(server.connections() <= BATCH_CONCURRENCY);
+    }
+    #[test]
+    fn parse() {
+        let raw = parse_raw(include_bytes!("../../test/STOCK_DAY.json")).unwrap();
+        let data = parse_month(raw, DuplicateDate::Reject, ParseMode::Strict).unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(data[0].date, NaiveDate::from_ymd_opt(2021, 1, 4).unwrap());
+        assert_eq!(data[0].volume, 39489959);
+        assert_eq!(data[0].close_price, price("536"));
+        assert_eq!(
+            data[0].vwap(),
+            Some(price("21127094445") / price("39489959"))
+        );
+        assert_eq!(data[0].sign, "+");
+        assert_eq!(data[0].direction, PriceDirection::Up);
+    }
+    #[test]
+    fn sign() {
+        for (cell, sign, direction, diff) in [
+            ("-3.00", "-", PriceDirection::Down, "-3"),
+            ("X0.00", "X", PriceDirection::NotComparable, "0"),
+            ("0.00", "", PriceDirection::Flat, "0"),
+        ] {
+            let raw = format!(
+                r#"{{"fields":{:?},"data":[["110/01/04","1","1","1","1","1","1","{}","1"]]}}"#,
+                FIELDS, cell
+            );
+            let raw = parse_raw(raw.as_bytes()).unwrap();
+            let data = parse_month(raw, DuplicateDate::default(), ParseMode::Strict).unwrap();
+            assert_eq!(data[0].sign, sign);
+            assert_eq!(data[0].direction, direction);
+            assert_eq!(data[0].diff, price(diff));
+        }
+    }
+    #[test]
+    fn malformed_date() {
+        for date in ["110/13/01", "110/02/30", "abc", "110/01", "-1/01/04", ""] {
+            let raw = format!(
+                r#"{{"fields":{:?},"data":[["{}","1","1","1","1","1","1","1","1"]]}}"#,
+                FIELDS, date
+            );
+            let raw = parse_raw(raw.as_bytes()).unwrap();
+            assert!(matches!(
+                parse_month(raw, DuplicateDate::default(), ParseMode::Strict),
+                Err(Error::IncompatibleApi { .. })
+            ));
+        }
+    }
+    #[test]
+    fn parse_mode() {
+        let raw = || {
+            let raw = format!(
+                r#"{{"fields":{:?},"data":[["110/01/04","1","1","1","1","1","1","1","1"],["110/01/05","N/A","1","1","1","1","1","1","1"]]}}"#,
+                FIELDS
+            );
+            parse_raw(raw.as_bytes()).unwrap()
+        };
+        assert!(parse_month(raw(), DuplicateDate::default(), ParseMode::Strict).is_err());
+        let data = parse_month(raw(), DuplicateDate::default(), ParseMode::Lenient).unwrap();
+        assert_eq!(data.len(), 1);
+    }
+    #[test]
+    fn csv_guard() {
+        let raw = format!(
+            r#"{{"fields":{:?},"data":[["=\"110/01/04\"","=\"2,330\"","1","1","1","1","=\"536.00\"","1","1"]]}}"#,
+            FIELDS
+        );
+        let raw = parse_raw(raw.as_bytes()).unwrap();
+        let data = parse_month(raw, DuplicateDate::default(), ParseMode::Strict).unwrap();
+        assert_eq!(data[0].volume, 2330);
+        assert_eq!(data[0].close_price, price("536"));
+    }
+    #[test]
+    fn schema() {
+        assert!(FieldMapper::new(FIELDS.into_iter()).is_ok());
+    }
+    #[test]
+    fn duplicate_date() {
+        let raw = || parse_raw(include_bytes!("../../test/STOCK_DAY.duplicate.json")).unwrap();
+        assert!(matches!(
+            parse_month(raw(), DuplicateDate::Reject, ParseMode::Strict),
+            Err(Error::IncompatibleApi { context }) if context == "duplicated date 2021-01-05"
+        ));
+        let data = parse_month(raw(), DuplicateDate::KeepLast, ParseMode::Strict).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1].close_price, price("555"));
+    }
+}
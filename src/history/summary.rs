@@ -0,0 +1,172 @@
+//! TWSE monthly trading summary of a stock (`FMSRFK`)
+
+use chrono::Month;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{parse_month, DailyData, History};
+use crate::table::{self, cell, Report};
+use crate::{
+    is_unknown_stock, roc_to_gregorian_year, Error, Family, MediaType, Price, Stock, StockKind,
+};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/exchangeReport/FMSRFK";
+/// column headers of `FMSRFK` this module reads
+pub(crate) const FIELDS: [&str; 9] = [
+    "年度",
+    "月份",
+    "最高價",
+    "最低價",
+    "加權(A/B)平均價",
+    "成交筆數",
+    "成交金額(A)",
+    "成交股數(B)",
+    "週轉率(%)",
+];
+
+/// Official monthly aggregates of a stock, as displayed on the TWSE website
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MonthlySummary {
+    /// highest price of the month
+    pub high_price: Price,
+    /// lowest price of the month
+    pub low_price: Price,
+    /// volume weighted average price (成交金額 / 成交股數)
+    pub average_price: Price,
+    /// number of matched trades (成交筆數)
+    pub transaction: u64,
+    /// total traded value in NT dollars
+    pub turnover: Price,
+    /// traded shares
+    pub volume: u64,
+    /// turnover rate in percent (週轉率)
+    pub turnover_rate: Price,
+    /// notes TWSE attaches to the daily report of the month
+    pub notes: Vec<String>,
+}
+
+/// parse the report and return the summary of every month in it with its gregorian year
+fn parse_report(body: &[u8]) -> Result<Vec<(i32, u32, MonthlySummary)>, Error> {
+    let report: Report = table::parse_json(body)?;
+    let [year, month, high_price, low_price, average_price, transaction, turnover, volume, turnover_rate] =
+        report.columns(FIELDS)?;
+    report
+        .data
+        .iter()
+        .map(|row| {
+            let summary = MonthlySummary {
+                high_price: cell(row, high_price)?,
+                low_price: cell(row, low_price)?,
+                average_price: cell(row, average_price)?,
+                transaction: cell(row, transaction)?,
+                turnover: cell(row, turnover)?,
+                volume: cell(row, volume)?,
+                turnover_rate: cell(row, turnover_rate)?,
+                notes: Vec::new(),
+            };
+            Ok((
+                roc_to_gregorian_year(cell(row, year)?),
+                cell(row, month)?,
+                summary,
+            ))
+        })
+        .collect()
+}
+
+impl History<'_> {
+    /// Fetch the trading history of a month together with its official summary
+    ///
+    /// Summary is `None` if TWSE hasn't published it yet, e.g. for the current month.
+    ///
+    /// OTC stock is read from TPEx the same way as [`History::fetch`], its summary is always
+    /// `None` as `FMSRFK` only covers TWSE.
+    pub async fn fetch_with_summary(
+        &self,
+        month: Month,
+        year: u16,
+        stock: Stock,
+    ) -> Result<(Vec<DailyData>, Option<MonthlySummary>), Error> {
+        if stock.kind == StockKind::OverTheCounter {
+            return Ok((self.fetch_tpex(month, year, stock).await?.0, None));
+        }
+        let (raw, body) = self.fetch_raw(month, year, stock.clone()).await?;
+        let notes = raw.notes.clone();
+        let data =
+            parse_month(raw, self.1, self.0.parse_mode).map_err(|err| err.with_body(&body))?;
+
+        let body = self
+            .0
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &format!("{:04}0101", year)),
+                    ("stockNo", &stock.code.to_string()),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+        let summary = parse_report(&body)
+            .map_err(|err| match err {
+                Error::StatMessage(stat) if is_unknown_stock(&stat) => {
+                    Error::UnknownStock(stock.code)
+                }
+                err => err.with_body(&body),
+            })?
+            .into_iter()
+            .find(|(y, m, _)| *y == year as i32 && *m == month.number_from_month())
+            .map(|(_, _, summary)| MonthlySummary { notes, ..summary });
+        Ok((data, summary))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price;
+
+    #[test]
+    fn parse() {
+        let data = parse_report(include_bytes!("../../test/FMSRFK.json")).unwrap();
+        assert_eq!(data.len(), 2);
+        let (year, month, summary) = &data[0];
+        assert_eq!((*year, *month), (2021, 1));
+        assert_eq!(summary.high_price, price("679"));
+        assert_eq!(summary.volume, 1714283017);
+        assert_eq!(summary.turnover, price("1040541820530"));
+        assert_eq!(summary.turnover_rate, price("6.61"));
+    }
+    #[tokio::test]
+    async fn fetch_with_summary() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let history = client.history();
+        let (data, summary) = history
+            .fetch_with_summary(Month::January, 2021, stock.clone())
+            .await
+            .unwrap();
+        let summary = summary.unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(summary.transaction, 1165489);
+        assert_eq!(summary.notes.len(), 3);
+        let (_, summary) = history
+            .fetch_with_summary(Month::March, 2021, stock)
+            .await
+            .unwrap();
+        assert!(summary.is_none());
+        // TPEx daily rows, no FMSRFK request
+        let (data, summary) = history
+            .fetch_with_summary(Month::January, 2021, Stock::otc(6488))
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 3);
+        assert!(summary.is_none());
+        assert_eq!(server.hits("/exchangeReport/FMSRFK"), 2);
+    }
+}
@@ -0,0 +1,149 @@
+//! TPEx monthly trading history of an OTC stock (`tradingStock`)
+
+use chrono::{Month, NaiveDate};
+use serde_json::Value;
+
+use super::{parse_signed, push_daily, DailyData, DuplicateDate, History, ResponseMeta};
+use crate::table::{self, parse_cell, Tables};
+use crate::{parse_roc_date, price_from, Error, Family, MediaType, ParseMode, Price, Stock};
+
+pub(crate) static ENDPOINT: &str = "https://www.tpex.org.tw/www/zh-tw/afterTrading/tradingStock";
+/// column headers of `tradingStock` this module reads, whitespace removed
+pub(crate) const FIELDS: [&str; 9] = [
+    "日期",
+    "成交張數",
+    "成交仟元",
+    "開盤",
+    "最高",
+    "最低",
+    "收盤",
+    "漲跌",
+    "筆數",
+];
+/// TPEx reports volume in lots (張) and turnover in thousand NT dollars (仟元)
+const UNIT: u64 = 1000;
+
+/// map a row by the [`columns`](table::columns) of [`FIELDS`]
+fn map(columns: &[usize; FIELDS.len()], row: &[Value]) -> Option<DailyData> {
+    let get = |i: usize| row.get(columns[i]);
+    let (sign, direction, diff) = parse_signed(get(7)?.as_str()?)?;
+    Some(DailyData {
+        date: parse_roc_date(get(0)?.as_str()?)?,
+        volume: parse_cell::<u64>(get(1)?)? * UNIT,
+        turnover: parse_cell::<Price>(get(2)?)? * price_from(UNIT),
+        open_price: parse_cell(get(3)?)?,
+        high_price: parse_cell(get(4)?)?,
+        low_price: parse_cell(get(5)?)?,
+        close_price: parse_cell(get(6)?)?,
+        diff,
+        transaction: parse_cell(get(8)?)?,
+        direction,
+        sign,
+    })
+}
+
+/// parse the report into daily data, empty if the stock didn't trade in the month
+fn parse_report(
+    body: &[u8],
+    policy: DuplicateDate,
+    mode: ParseMode,
+) -> Result<(Vec<DailyData>, ResponseMeta), Error> {
+    let report: Tables = table::parse_json(body)?;
+    let meta = ResponseMeta { stat: report.stat };
+    let Some(table) = report.tables.into_iter().next() else {
+        return Ok((Vec::new(), meta));
+    };
+    if table.data.is_empty() {
+        return Ok((Vec::new(), meta));
+    }
+    let columns = table::columns(&table.fields, FIELDS).ok_or(Error::incompatible())?;
+    let mut result = Vec::with_capacity(table.data.len());
+    for row in table.data.iter() {
+        match map(&columns, row) {
+            Some(daily) => push_daily(&mut result, daily, policy)?,
+            None if mode == ParseMode::Lenient => {}
+            None => return Err(Error::incompatible()),
+        }
+    }
+    Ok((result, meta))
+}
+
+impl History<'_> {
+    /// [`History::fetch_with_meta`] of an OTC stock
+    pub(super) async fn fetch_tpex(
+        &self,
+        month: Month,
+        year: u16,
+        stock: Stock,
+    ) -> Result<(Vec<DailyData>, ResponseMeta), Error> {
+        stock.validate()?;
+        let date = NaiveDate::from_ymd_opt(year as i32, month.number_from_month(), 1)
+            .ok_or(Error::DateDoesNotExist)?
+            .format("%Y/%m/%d")
+            .to_string();
+        let body = self
+            .0
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &date),
+                    ("code", &stock.code.to_string()),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+        parse_report(&body, self.1, self.0.parse_mode).map_err(|err| err.with_body(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{history::PriceDirection, price, StockKind};
+
+    fn parse(body: &[u8]) -> Result<Vec<DailyData>, Error> {
+        parse_report(body, DuplicateDate::Reject, ParseMode::Strict).map(|(data, _)| data)
+    }
+
+    #[test]
+    fn parse_fixture() {
+        let data = parse(include_bytes!("../../test/tradingStock.json")).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0].date, NaiveDate::from_ymd_opt(2021, 1, 4).unwrap());
+        assert_eq!(data[0].volume, 1_523_000);
+        assert_eq!(data[0].turnover, price("803512000"));
+        assert_eq!(data[1].direction, PriceDirection::Down);
+        assert_eq!(data[1].diff, price("-4"));
+        assert_eq!(data[1].diff_cents(), -400);
+        assert!(parse(br#"{"tables":[{"fields":[],"data":[]}]}"#)
+            .unwrap()
+            .is_empty());
+    }
+    #[tokio::test]
+    async fn fetch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let stock = Stock {
+            kind: StockKind::OverTheCounter,
+            code: 6488,
+        };
+        let data = server
+            .client()
+            .history()
+            .fetch(Month::January, 2021, stock.clone())
+            .await
+            .unwrap();
+        assert!(!data.is_empty());
+        let (_, meta) = server
+            .client()
+            .history()
+            .fetch_with_meta(Month::January, 2021, stock)
+            .await
+            .unwrap();
+        assert_eq!(meta.stat, "ok");
+        assert!(!meta.has_warning());
+        assert_eq!(server.hits("/www/zh-tw/afterTrading/tradingStock"), 2);
+        assert_eq!(server.hits("/exchangeReport/STOCK_DAY"), 0);
+    }
+}
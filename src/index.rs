@@ -0,0 +1,96 @@
+//! TWSE daily market index (TAIEX) API
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{history::CategoryIndex, is_no_data, Client, Error};
+
+/// chinese name of TAIEX in the `MI_INDEX` price index table
+const TAIEX_NAME: &str = "發行量加權股價指數";
+
+/// Closing TAIEX (發行量加權股價指數) of a day together with the other indices
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DailyIndex {
+    /// Be noted that `date` is `UTC+8`
+    pub date: NaiveDate,
+    /// closing value
+    pub close: f64,
+    /// change from previous day, negative if down
+    pub diff: f64,
+    /// change from previous day in percent
+    pub change_percent: f64,
+    /// every other price index of the day, category (sector) index has its `industry` set
+    pub categories: Vec<CategoryIndex>,
+}
+
+/// newtype wrapper for the [`Client`] facilitating market index api
+pub struct IndexReport<'a>(&'a Client);
+
+impl Client {
+    /// Get the market index API client
+    pub fn index(&self) -> IndexReport<'_> {
+        IndexReport(self)
+    }
+}
+
+impl IndexReport<'_> {
+    /// Fetch the closing TAIEX and sub-indices on a specific day
+    ///
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch(&self, date: NaiveDate) -> Result<DailyIndex, Error> {
+        let indices = match self.0.history().fetch_category_indices(date).await {
+            Ok(x) => x,
+            Err(Error::StatMessage(stat)) if is_no_data(&stat) => return Err(Error::MarketClosed),
+            Err(err) => return Err(err),
+        };
+        split_taiex(date, indices)
+    }
+}
+
+/// take TAIEX out of `indices`, the rest become categories
+fn split_taiex(date: NaiveDate, mut indices: Vec<CategoryIndex>) -> Result<DailyIndex, Error> {
+    let taiex = indices
+        .iter()
+        .position(|x| x.name == TAIEX_NAME)
+        .map(|i| indices.remove(i))
+        .ok_or(Error::incompatible())?;
+    Ok(DailyIndex {
+        date,
+        close: taiex.close,
+        diff: taiex.diff,
+        change_percent: taiex.change_percent,
+        categories: indices,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 7, 15).unwrap()
+    }
+
+    #[tokio::test]
+    async fn fetch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let data = client.index().fetch(date()).await.unwrap();
+        assert_eq!(data.close, 23916.93);
+        assert_eq!(data.diff, 220.29);
+        assert_eq!(data.categories.len(), 2);
+        assert!(data.categories.iter().all(|x| x.name != TAIEX_NAME));
+
+        server.respond(
+            "/exchangeReport/MI_INDEX",
+            200,
+            r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#,
+        );
+        assert!(matches!(
+            client.index().fetch(date()).await,
+            Err(Error::MarketClosed)
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+//! TWSE three major institutional investors (三大法人) daily net buy/sell API
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::table::{self, cell, DailyCache, Report};
+use crate::{Client, Error, Family, MediaType, Stock, StockKind};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/rwd/zh/fund/T86";
+/// column headers of `T86` this module reads
+pub(crate) const FIELDS: [&str; 6] = [
+    "證券代號",
+    "外陸資買賣超股數(不含外資自營商)",
+    "外資自營商買賣超股數",
+    "投信買賣超股數",
+    "自營商買賣超股數",
+    "三大法人買賣超股數",
+];
+
+/// Net shares bought (positive) or sold (negative) by institutional investors in a day
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InstitutionalNet {
+    /// Be noted that `date` is `UTC+8`
+    pub date: NaiveDate,
+    /// foreign investors, including foreign dealers (外資及陸資)
+    pub foreign_net: i64,
+    /// investment trusts (投信)
+    pub trust_net: i64,
+    /// dealers (自營商), both proprietary and hedging
+    pub dealer_net: i64,
+    /// sum of all three
+    pub total_net: i64,
+}
+
+/// parse the report and return the net of every stock of the day
+fn parse_report(body: &[u8], date: NaiveDate) -> Result<Vec<(Stock, InstitutionalNet)>, Error> {
+    let report: Report = table::parse_daily(body)?;
+    let [code, foreign, foreign_dealer, trust, dealer, total] = report.columns(FIELDS)?;
+    table::listed_rows(&report.data, code, StockKind::Live)
+        .map(|row| {
+            let (stock, row) = row?;
+            let net = InstitutionalNet {
+                date,
+                foreign_net: cell::<i64>(row, foreign)? + cell::<i64>(row, foreign_dealer)?,
+                trust_net: cell(row, trust)?,
+                dealer_net: cell(row, dealer)?,
+                total_net: cell(row, total)?,
+            };
+            Ok((stock, net))
+        })
+        .collect()
+}
+
+/// Wrapper of the [`Client`] facilitating institutional investors api
+///
+/// Each day's report is cached, see [daily reports](crate#daily-reports).
+pub struct Institutional<'a> {
+    client: &'a Client,
+    cache: DailyCache<InstitutionalNet>,
+}
+
+impl Client {
+    /// Get the institutional investors API client
+    pub fn institutional(&self) -> Institutional<'_> {
+        Institutional {
+            client: self,
+            cache: DailyCache::new(),
+        }
+    }
+}
+
+impl Institutional<'_> {
+    /// Fetch the net of every listed stock on a specific day
+    ///
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch_all(
+        &self,
+        date: NaiveDate,
+    ) -> Result<Vec<(Stock, InstitutionalNet)>, Error> {
+        self.cache.get_or_fetch(date, self.fetch_report(date)).await
+    }
+    /// Fetch the net of a stock on a specific day
+    ///
+    /// A stock not in the report had no institutional activity that day, so every net is zero.
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch(&self, date: NaiveDate, stock: Stock) -> Result<InstitutionalNet, Error> {
+        Ok(self.find(date, &stock).await?.unwrap_or(InstitutionalNet {
+            date,
+            ..Default::default()
+        }))
+    }
+    async fn find(
+        &self,
+        date: NaiveDate,
+        stock: &Stock,
+    ) -> Result<Option<InstitutionalNet>, Error> {
+        Ok(table::find(self.fetch_all(date).await?, stock))
+    }
+    /// Fetch the daily net of a stock on every trading day between `from` and `to`
+    /// (inclusive), for spotting accumulation or distribution
+    ///
+    /// Holidays are skipped, so is a day the stock is not in the report.
+    pub async fn fetch_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        stock: Stock,
+    ) -> Result<Vec<InstitutionalNet>, Error> {
+        table::range(from, to, |date| self.find(date, &stock)).await
+    }
+    async fn fetch_report(&self, date: NaiveDate) -> Result<Vec<(Stock, InstitutionalNet)>, Error> {
+        let body = self
+            .client
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &date.format("%Y%m%d").to_string()),
+                    ("selectType", "ALLBUT0999"),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+        parse_report(&body, date).map_err(|err| err.with_body(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::tests::{date, stock};
+
+    #[test]
+    fn parse() {
+        let data = parse_report(include_bytes!("../test/T86.json"), date()).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0].0, stock());
+        assert_eq!(data[0].1.foreign_net, 5222123);
+        assert_eq!(data[1].1.foreign_net, -4499000);
+        assert_eq!(data[1].1.total_net, -4709000);
+        assert!(matches!(
+            parse_report(
+                r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#.as_bytes(),
+                date()
+            ),
+            Err(Error::MarketClosed)
+        ));
+    }
+    #[tokio::test]
+    async fn fetch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let institutional = client.institutional();
+        let net = institutional.fetch(date(), stock()).await.unwrap();
+        assert_eq!(net.foreign_net, 5222123);
+        assert_eq!(net.date, date());
+        let inactive = Stock {
+            kind: StockKind::Live,
+            code: 1101,
+        };
+        let net = institutional.fetch(date(), inactive).await.unwrap();
+        assert_eq!(
+            net,
+            InstitutionalNet {
+                date: date(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(server.hits("/rwd/zh/fund/T86"), 1);
+    }
+    #[tokio::test]
+    async fn fetch_range() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let institutional = client.institutional();
+        let data = institutional
+            .fetch_range(
+                date(),
+                NaiveDate::from_ymd_opt(2024, 7, 21).unwrap(),
+                stock(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(data[4].date, NaiveDate::from_ymd_opt(2024, 7, 19).unwrap());
+        // cached across stocks
+        let other = Stock {
+            kind: StockKind::Live,
+            code: 2317,
+        };
+        institutional
+            .fetch_range(date(), NaiveDate::from_ymd_opt(2024, 7, 21).unwrap(), other)
+            .await
+            .unwrap();
+        assert_eq!(server.hits("/rwd/zh/fund/T86"), 5);
+    }
+}
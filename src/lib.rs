@@ -27,43 +27,306 @@
 //! }
 //! ```
 //!
+//! # Daily reports:
+//! [`institutional`], [`short_sale`], [`margin`] and [`foreign`] read a report TWSE publishes
+//! every trading day:
+//! - `fetch_all(date)` returns every listed stock in the report
+//! - `fetch(date, stock)` of a stock not in the report returns every figure zero, as the stock
+//!   had nothing to report that day
+//! - [`Error::MarketClosed`] if `date` is not a trading day
+//!
+//! Each day's report is cached in the wrapper, so keep it around to fetch many stocks
+//! over the same range with one request per day.
+//!
 //! # Features:
 //! - `serde`: Enable serde support
 //! - `native-tls`: Use the native-tls backend
 //! - `native-tls-vendored`: Use the native-tls backend with vendored OpenSSL
 //! - `rustls-tls`: Use the rustls backend
+//! - `test-util`: Export a mock TWSE server for testing without network
+//! - `decimal`: Use exact [`rust_decimal::Decimal`] instead of `f64` for [`Price`]
+//! - `gzip`: Decompress gzip body sent without `Content-Encoding`
+//! - `polars`: Convert fetched data into a polars `DataFrame`, see `dataframe` module
+//! - `csv`: Write fetched data as CSV, see `export` module
 //!
 //! Don't forget to disable default features if you want to use a specific TLS backend.
 
+mod builder;
+pub mod company;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+pub mod dividend;
+#[cfg(feature = "csv")]
+pub mod export;
+pub mod foreign;
 pub mod history;
+pub mod index;
+pub mod institutional;
 pub mod list;
+pub mod margin;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
 pub mod realtime;
+mod retry;
+mod schema;
+pub mod short_sale;
+mod table;
+
+pub use builder::ClientBuilder;
+pub use retry::{retry, RetryPolicy};
+use retry::{RateLimiter, SharedBackoff};
+pub use schema::{supported_schema, SchemaInfo};
 
+use std::time::{Duration, Instant};
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use itertools::Itertools;
 use reqwest::Client as HttpClient;
 
 fn get_time_zone() -> chrono::FixedOffset {
     chrono::FixedOffset::east_opt(8 * 3600).unwrap()
 }
 
+/// Conversion from a JSON number, mirroring `as_u64`, `as_i64` and `as_f64`
+pub(crate) trait FromJsonNumber: Sized {
+    fn from_json_number(x: &serde_json::Number) -> Option<Self>;
+}
+
+impl FromJsonNumber for u64 {
+    fn from_json_number(x: &serde_json::Number) -> Option<Self> {
+        x.as_u64()
+    }
+}
+
+impl FromJsonNumber for i64 {
+    fn from_json_number(x: &serde_json::Number) -> Option<Self> {
+        x.as_i64()
+    }
+}
+
+impl FromJsonNumber for f64 {
+    fn from_json_number(x: &serde_json::Number) -> Option<Self> {
+        x.as_f64()
+    }
+}
+
+/// From the text of the number, not through `f64`
+#[cfg(feature = "decimal")]
+impl FromJsonNumber for rust_decimal::Decimal {
+    fn from_json_number(x: &serde_json::Number) -> Option<Self> {
+        x.to_string().parse().ok()
+    }
+}
+
+/// Price or other amount of NT dollars, e.g. closing price or turnover
+///
+/// `f64` by default, [`rust_decimal::Decimal`] parsed straight from the upstream text
+/// with the `decimal` feature.
+#[cfg(not(feature = "decimal"))]
+pub type Price = f64;
+/// Price or other amount of NT dollars, e.g. closing price or turnover
+///
+/// `f64` by default, [`rust_decimal::Decimal`] parsed straight from the upstream text
+/// with the `decimal` feature.
+#[cfg(feature = "decimal")]
+pub type Price = rust_decimal::Decimal;
+
+/// `x` as [`Price`]
+pub(crate) fn price_from(x: u64) -> Price {
+    #[cfg(not(feature = "decimal"))]
+    return x as f64;
+    #[cfg(feature = "decimal")]
+    return x.into();
+}
+
+/// `x` in hundredths of NT dollar, rounded to the nearest
+pub(crate) fn price_to_cents(x: Price) -> i64 {
+    #[cfg(not(feature = "decimal"))]
+    return (x * 100.0).round() as i64;
+    #[cfg(feature = "decimal")]
+    return rust_decimal::prelude::ToPrimitive::to_i64(&(x * Price::ONE_HUNDRED).round())
+        .unwrap_or_default();
+}
+
+/// `x` hundredths of NT dollar as [`Price`]
+pub(crate) fn price_from_cents(x: i64) -> Price {
+    #[cfg(not(feature = "decimal"))]
+    return x as f64 / 100.0;
+    #[cfg(feature = "decimal")]
+    return Price::new(x, 2);
+}
+
+/// `x` as `f64`, for ratios which don't need to be exact
+pub(crate) fn price_to_f64(x: Price) -> f64 {
+    #[cfg(not(feature = "decimal"))]
+    return x;
+    #[cfg(feature = "decimal")]
+    return rust_decimal::prelude::ToPrimitive::to_f64(&x).unwrap_or(f64::NAN);
+}
+
+/// [`Price`] of a literal, e.g. `price("536.00")`
+#[cfg(test)]
+pub(crate) fn price(x: &str) -> Price {
+    x.parse().unwrap()
+}
+
+/// Gregorian year of ROC (民國) year 0, TWSE reports dates as ROC years
+pub const ROC_EPOCH_YEAR: i32 = 1911;
+
+/// Convert ROC (民國) year to Gregorian year, e.g. `110` to `2021`
+pub fn roc_to_gregorian_year(roc: u32) -> i32 {
+    roc as i32 + ROC_EPOCH_YEAR
+}
+
+/// Strip the `="..."` wrapper TWSE uses to stop spreadsheet from interpreting a cell
+pub(crate) fn strip_csv_guard(value: &str) -> &str {
+    value
+        .strip_prefix("=\"")
+        .and_then(|x| x.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Whether upstream `stat` means there is no data for the query, e.g. a holiday
+pub(crate) fn is_no_data(stat: &str) -> bool {
+    stat.contains("沒有符合條件的資料")
+}
+
+/// Whether upstream `stat` means the stock code doesn't exist, e.g. `查無此股票代號`
+pub(crate) fn is_unknown_stock(stat: &str) -> bool {
+    stat.contains("代號") && ["查無", "錯誤", "不存在"].iter().any(|x| stat.contains(x))
+}
+
+/// Parse ROC (民國) date like `110/01/04`
+pub(crate) fn parse_roc_date(value: &str) -> Option<chrono::NaiveDate> {
+    let (y, m, d) = strip_csv_guard(value.trim())
+        .split('/')
+        .map(|x| x.parse::<u32>().ok())
+        .collect_tuple()?;
+    chrono::NaiveDate::from_ymd_opt(roc_to_gregorian_year(y?), m?, d?)
+}
+
+/// Every weekday between `start` and `end` (inclusive), holidays are only known from upstream
+pub(crate) fn weekdays(start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+    start
+        .iter_days()
+        .take_while(|x| *x <= end)
+        .filter(|x| !matches!(x.weekday(), Weekday::Sat | Weekday::Sun))
+        .collect()
+}
+
 /// Error type that may occur when interacting with the TWSE API
+///
+/// Public `fetch*` methods never panic on upstream response, every failure is reported as [`Error`]
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    /// Request or response timed out
+    #[error("request timed out")]
+    Timeout(#[source] reqwest::Error),
+    /// Failed to connect to upstream
+    #[error("failed to connect")]
+    Connect(#[source] reqwest::Error),
+    /// Failed to read or decode the response body
+    #[error("failed to decode response")]
+    Decode(#[source] reqwest::Error),
+    /// Any other HTTP error
     #[error(transparent)]
-    Reqwest(#[from] reqwest::Error),
-    #[error("Rate limit exceeded")]
-    RateLimitExceeded,
+    Reqwest(reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Upstream responded `429 Too Many Requests` to every attempt, including retries of
+    /// [`Client::with_retry`]
+    #[error("Rate limit exceeded after {attempts} attempts")]
+    RateLimitExceeded { attempts: u32 },
+    /// Upstream responded `5xx`, usually a transient failure
+    #[error("server error `{0}`")]
+    ServerError(reqwest::StatusCode),
+    /// Upstream responded a non-success status other than `429` and `5xx`, e.g. `404`
+    /// or a redirect not followed
+    #[error("unexpected status `{0}`")]
+    UnexpectedStatus(reqwest::StatusCode),
     /// Incompatible API, the upstream API has changed
-    #[error("incompatible upstream api")]
-    IncompatibleApi,
+    ///
+    /// `context` is the start of the offending body (at most [`CONTEXT_LEN`] bytes) when
+    /// it's available, empty otherwise.
+    #[error("incompatible upstream api{}", describe_context(context))]
+    IncompatibleApi { context: String },
     #[error("date does not exist")]
     DateDoesNotExist,
     #[error("Error message from upstream: `{0}`")]
     StatMessage(String),
     #[error("market is closed")]
     MarketClosed,
+    /// String isn't a MIS channel like `tse_2330.tw`
+    #[error("invalid channel `{0}`")]
+    InvalidChannel(String),
+    /// Stock code has more than six digits (or is zero), see [`Stock::validate`]
+    #[error("invalid stock code `{0}`")]
+    InvalidStockCode(u32),
+    /// Stock isn't in the list of currently tradable stocks, or upstream doesn't know the code
+    #[error("unknown stock `{0}`")]
+    UnknownStock(u32),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(value: reqwest::Error) -> Self {
+        if value.is_timeout() {
+            Error::Timeout(value)
+        } else if value.is_connect() {
+            Error::Connect(value)
+        } else if value.is_decode() || value.is_body() {
+            Error::Decode(value)
+        } else {
+            Error::Reqwest(value)
+        }
+    }
+}
+
+/// Largest code of six digits, see [`Stock::validate`]
+const MAX_STOCK_CODE: u32 = 999_999;
+
+/// Max length of [`Error::IncompatibleApi::context`], in bytes
+pub const CONTEXT_LEN: usize = 200;
+
+fn describe_context(context: &str) -> String {
+    match context.is_empty() {
+        true => String::new(),
+        false => format!(", got `{}`", context),
+    }
 }
 
-#[derive(Debug, Hash, Clone, PartialEq, PartialOrd, Default)]
+impl Error {
+    /// [`Error::IncompatibleApi`] without context, see [`Error::with_body`]
+    pub(crate) fn incompatible() -> Self {
+        Error::IncompatibleApi {
+            context: String::new(),
+        }
+    }
+    /// Attach the start of `body` to [`Error::IncompatibleApi`] that has no context yet,
+    /// other error is returned as is
+    pub(crate) fn with_body(self, body: &[u8]) -> Self {
+        match self {
+            Error::IncompatibleApi { context } if context.is_empty() => {
+                let context = String::from_utf8_lossy(&body[..body.len().min(CONTEXT_LEN)]);
+                Error::IncompatibleApi {
+                    context: context.into_owned(),
+                }
+            }
+            err => err,
+        }
+    }
+    /// Whether the failure is transient and worth retrying
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Error::RateLimitExceeded { .. }
+                | Error::ServerError(_)
+                | Error::Timeout(_)
+                | Error::Connect(_)
+        )
+    }
+}
+
+#[derive(Debug, Hash, Clone, PartialEq, Eq, PartialOrd, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Stock identifier and its variant
 pub struct Stock {
@@ -71,26 +334,552 @@ pub struct Stock {
     pub code: u32,
 }
 
+impl Stock {
+    /// Listed (上市) stock of `code`
+    pub fn live(code: u32) -> Self {
+        Stock {
+            kind: StockKind::Live,
+            code,
+        }
+    }
+    /// OTC (上櫃) stock of `code`
+    pub fn otc(code: u32) -> Self {
+        Stock {
+            kind: StockKind::OverTheCounter,
+            code,
+        }
+    }
+    /// Stock of `code` assumed to be listed, see [`list::List::resolve`] to look up the
+    /// actual market
+    pub fn from_code(code: u32) -> Self {
+        Self::live(code)
+    }
+    /// Every stock of `kind` with code in `codes`, e.g. to feed into [`realtime::RealTime::fetch_batch`]
+    pub fn range(
+        kind: StockKind,
+        codes: std::ops::RangeInclusive<u32>,
+    ) -> impl Iterator<Item = Stock> {
+        codes.map(move |code| Stock {
+            kind: kind.clone(),
+            code,
+        })
+    }
+    /// Check the code is non-zero and at most six digits, as listed equities have four and
+    /// ETFs and other instruments up to six
+    ///
+    /// Leading zeros are not kept in `code` (e.g. `0050` is `50`), so shorter code passes.
+    /// Every fetch checks this before sending the request.
+    pub fn validate(&self) -> Result<(), Error> {
+        match self.code {
+            1..=MAX_STOCK_CODE => Ok(()),
+            code => Err(Error::InvalidStockCode(code)),
+        }
+    }
+    /// Channel string of the MIS realtime API, `tse_{code}.tw` or `otc_{code}.tw`
+    ///
+    /// ```
+    /// use twstock::*;
+    ///
+    /// let stock = Stock {
+    ///     kind: StockKind::OverTheCounter,
+    ///     code: 6488,
+    /// };
+    /// assert_eq!(stock.to_twse_channel(), "otc_6488.tw");
+    /// assert_eq!("otc_6488.tw".parse::<Stock>().unwrap(), stock);
+    /// ```
+    pub fn to_twse_channel(&self) -> String {
+        let prefix = match self.kind {
+            StockKind::Live => "tse",
+            StockKind::OverTheCounter => "otc",
+        };
+        format!("{}_{}.tw", prefix, self.code)
+    }
+}
+
+impl std::str::FromStr for Stock {
+    type Err = Error;
+
+    /// Parse a channel string produced by [`Stock::to_twse_channel`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidChannel(s.to_string());
+        let (prefix, rest) = s.split_once('_').ok_or_else(invalid)?;
+        let kind = match prefix {
+            "tse" => StockKind::Live,
+            "otc" => StockKind::OverTheCounter,
+            _ => return Err(invalid()),
+        };
+        let code = rest
+            .strip_suffix(".tw")
+            .filter(|x| x.chars().all(|c| c.is_ascii_digit()))
+            .and_then(|x| x.parse().ok())
+            .ok_or_else(invalid)?;
+        Ok(Stock { kind, code })
+    }
+}
+
 #[derive(Debug, Hash, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// variant of stock
 ///
 /// The number is intentionally set to match the value used in the upstream API
+///
+/// With `serde` feature, it's serialized as the exchange, `"TSE"` or `"OTC"`.
 pub enum StockKind {
     #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "TSE", alias = "Live"))]
     Live = 2,
+    #[cfg_attr(feature = "serde", serde(rename = "OTC", alias = "OverTheCounter"))]
     OverTheCounter = 4,
 }
 
+/// Family of upstream endpoints sharing the same rate-limit behavior
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Family {
+    /// MIS realtime endpoint (`mis.twse.com.tw`)
+    RealTime,
+    /// report endpoints (history, list, company...)
+    Report,
+}
+
+/// Media type expected from an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MediaType {
+    Json,
+    Html,
+    Csv,
+}
+
+impl MediaType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Json => "application/json",
+            MediaType::Html => "text/html",
+            MediaType::Csv => "text/csv",
+        }
+    }
+    /// Reject response of other `Content-Type` (e.g. maintenance page), missing header is accepted
+    fn check(&self, response: &reqwest::Response) -> Result<(), Error> {
+        let Some(actual) = response.headers().get(reqwest::header::CONTENT_TYPE) else {
+            return Ok(());
+        };
+        let actual = String::from_utf8_lossy(actual.as_bytes()).to_ascii_lowercase();
+        // CSV is sometimes sent as a download
+        if actual.contains(self.as_str())
+            || (*self == MediaType::Csv && actual.contains("application/octet-stream"))
+        {
+            return Ok(());
+        }
+        log::warn!(
+            "expected `{}` from {} but got `{}`",
+            self.as_str(),
+            response.url(),
+            actual
+        );
+        Err(Error::incompatible())
+    }
+}
+
+/// Leading bytes of a gzip stream, occasionally sent by TWSE without `Content-Encoding`
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decompress a gzip body reqwest didn't decode
+#[cfg(feature = "gzip")]
+fn gunzip(body: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+    let mut buf = Vec::new();
+    flate2::read::GzDecoder::new(body).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(_: &[u8]) -> std::io::Result<Vec<u8>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "`gzip` feature is disabled",
+    ))
+}
+
+/// Cache validators of a response, to ask upstream for the data only if it has changed
+///
+/// Both are `None` if upstream doesn't send them, then every request downloads the data.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Validator {
+    /// `ETag` header, sent back as `If-None-Match`
+    pub etag: Option<String>,
+    /// `Last-Modified` header, sent back as `If-Modified-Since`
+    pub last_modified: Option<String>,
+}
+
+impl Validator {
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let get = |name| {
+            headers
+                .get(name)
+                .and_then(|x| x.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: get(reqwest::header::ETAG),
+            last_modified: get(reqwest::header::LAST_MODIFIED),
+        }
+    }
+    fn apply(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(x) = &self.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, x);
+        }
+        if let Some(x) = &self.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, x);
+        }
+        request
+    }
+}
+
 /// Client for fetching data from the Taiwan Stock Exchange (TWSE) API
-#[derive(Default)]
-pub struct Client(HttpClient);
+pub struct Client {
+    http: HttpClient,
+    realtime_retry: RetryPolicy,
+    report_retry: RetryPolicy,
+    realtime_backoff: SharedBackoff,
+    report_backoff: SharedBackoff,
+    aggressive_polling: bool,
+    base_url: Option<String>,
+    parse_mode: ParseMode,
+    report_format: ReportFormat,
+    strict_industry: bool,
+    /// cap of in-flight requests shared by every module, see [`ClientBuilder::max_concurrency`]
+    concurrency: Option<tokio::sync::Semaphore>,
+    /// pace of requests shared by every module, see [`ClientBuilder::rate_limit`]
+    rate_limiter: Option<RateLimiter>,
+}
+
+/// How to handle data that can't be parsed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// any unparseable field returns [`Error::IncompatibleApi`]
+    #[default]
+    Strict,
+    /// skip the unparseable row, or use `None` for the field if its type allows
+    Lenient,
+}
+
+/// Format requested from report endpoints, see [`Client::with_report_format`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    /// Big5 encoded CSV, a fallback when the JSON schema breaks
+    Csv,
+}
+
+impl ReportFormat {
+    /// value of the `response` query
+    pub(crate) fn as_query(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        }
+    }
+    pub(crate) fn media_type(&self) -> MediaType {
+        match self {
+            ReportFormat::Json => MediaType::Json,
+            ReportFormat::Csv => MediaType::Csv,
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        ClientBuilder::new()
+            .build()
+            .expect("TLS backend cannot be initialized")
+    }
+}
 
 impl Client {
     /// Create a new client
     pub fn new() -> Self {
         Self::default()
     }
+    /// Get a builder to configure the underlying HTTP client
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+    fn from_http(http: HttpClient) -> Self {
+        Self {
+            http,
+            realtime_retry: RetryPolicy::default(),
+            report_retry: RetryPolicy::default(),
+            realtime_backoff: SharedBackoff::default(),
+            report_backoff: SharedBackoff::default(),
+            aggressive_polling: false,
+            base_url: None,
+            parse_mode: ParseMode::default(),
+            report_format: ReportFormat::default(),
+            strict_industry: false,
+            concurrency: None,
+            rate_limiter: None,
+        }
+    }
+    /// Retry rate-limited request of every module up to `max_retries` times, backing off
+    /// exponentially from `base_delay`
+    ///
+    /// Same as [`Client::with_retry_policy`] with [`RetryPolicy::new`].
+    pub fn with_retry(self, max_retries: u32, base_delay: Duration) -> Self {
+        self.with_retry_policy(RetryPolicy::new(max_retries, base_delay))
+    }
+    /// Retry rate-limited request of every module with `policy`
+    ///
+    /// A rate-limited request also pauses every other request of the same endpoint family
+    /// for the retry delay, so concurrent requests back off together.
+    pub fn with_retry_policy(self, policy: RetryPolicy) -> Self {
+        self.with_realtime_retry(policy).with_report_retry(policy)
+    }
+    /// Retry rate-limited request of [`realtime`] module with `policy`
+    ///
+    /// See [`RetryPolicy::realtime`] for a preset
+    pub fn with_realtime_retry(mut self, policy: RetryPolicy) -> Self {
+        self.realtime_retry = policy;
+        self
+    }
+    /// Retry rate-limited request of report modules ([`history`], [`list`], [`company`]) with `policy`
+    ///
+    /// See [`RetryPolicy::report`] for a preset
+    pub fn with_report_retry(mut self, policy: RetryPolicy) -> Self {
+        self.report_retry = policy;
+        self
+    }
+    /// Allow polling realtime data faster than [`realtime::MIN_POLL_INTERVAL`]
+    ///
+    /// Be noted that TWSE bans IP polling too aggressively
+    pub fn allow_aggressive_polling(mut self) -> Self {
+        self.aggressive_polling = true;
+        self
+    }
+    /// Set how data that can't be parsed is handled
+    pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        self.parse_mode = mode;
+        self
+    }
+    /// Set the format requested from report endpoints, JSON by default
+    ///
+    /// Only [`history::History`] monthly fetches support [`ReportFormat::Csv`] for now,
+    /// other modules keep requesting JSON.
+    pub fn with_report_format(mut self, format: ReportFormat) -> Self {
+        self.report_format = format;
+        self
+    }
+    /// Return [`Error::IncompatibleApi`] when the list contains an industry the crate
+    /// doesn't know, instead of bucketing it into [`list::Industry::Other`]
+    pub fn with_strict_industry(mut self, strict: bool) -> Self {
+        self.strict_industry = strict;
+        self
+    }
+    /// Send every request to `base_url` instead of TWSE, keeping the path
+    ///
+    /// Useful for a mirror, a proxy or a mock server (see `mock` module behind `test-util` feature)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+    /// Resolve `endpoint` against the base url override
+    fn endpoint(&self, endpoint: &str) -> String {
+        let Some(base_url) = &self.base_url else {
+            return endpoint.to_string();
+        };
+        let path = reqwest::Url::parse(endpoint)
+            .map(|x| x.path().to_string())
+            .unwrap_or_default();
+        format!("{}{}", base_url.trim_end_matches('/'), path)
+    }
+    /// Send a GET request and read the body, retrying according to the policy of `family`
+    ///
+    /// Successful response not in `media_type` is [`Error::IncompatibleApi`].
+    /// Rate-limited (`429`) response pauses every request of `family` and is retried, see
+    /// [`SharedBackoff`], any other failed status is returned immediately.
+    pub(crate) async fn get(
+        &self,
+        family: Family,
+        endpoint: &str,
+        query: &[(&str, &str)],
+        media_type: MediaType,
+    ) -> Result<Vec<u8>, Error> {
+        self.get_if_modified(family, endpoint, query, media_type, None)
+            .await?
+            .map(|(body, _)| body)
+            .ok_or(Error::incompatible())
+    }
+    /// Same as [`Client::get`], sending a conditional request if `validator` is given
+    ///
+    /// `None` if upstream answered `304 Not Modified`.
+    pub(crate) async fn get_if_modified(
+        &self,
+        family: Family,
+        endpoint: &str,
+        query: &[(&str, &str)],
+        media_type: MediaType,
+        validator: Option<&Validator>,
+    ) -> Result<Option<(Vec<u8>, Validator)>, Error> {
+        let (policy, backoff) = match family {
+            Family::RealTime => (self.realtime_retry, &self.realtime_backoff),
+            Family::Report => (self.report_retry, &self.report_backoff),
+        };
+        let endpoint = self.endpoint(endpoint);
+        let mut attempt = 0;
+        loop {
+            backoff.wait().await;
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+            // held until the body is read, so the connection is back to the pool
+            let _permit = match &self.concurrency {
+                Some(x) => x.acquire().await.ok(),
+                None => None,
+            };
+            let mut request = self
+                .http
+                .get(&endpoint)
+                .query(query)
+                .header(reqwest::header::ACCEPT, media_type.as_str());
+            if let Some(validator) = validator {
+                request = validator.apply(request);
+            }
+            let response = request.send().await?;
+            let status = response.status();
+            if status == reqwest::StatusCode::NOT_MODIFIED && validator.is_some() {
+                return Ok(None);
+            }
+            if status.is_success() {
+                let next = Validator::from_headers(response.headers());
+                let url = response.url().clone();
+                let is_encoded = response
+                    .headers()
+                    .contains_key(reqwest::header::CONTENT_ENCODING);
+                let check = media_type.check(&response);
+                let body = response.bytes().await?;
+                // `Content-Type` of such body can't be trusted either
+                if !is_encoded && body.starts_with(&GZIP_MAGIC) {
+                    let body = gunzip(&body).map_err(|err| {
+                        log::warn!("{} sent gzip body without `Content-Encoding`: {}", url, err);
+                        Error::incompatible()
+                    })?;
+                    return Ok(Some((body, next)));
+                }
+                check.map_err(|err| err.with_body(&body))?;
+                return Ok(Some((body.to_vec(), next)));
+            }
+            if status.is_server_error() {
+                return Err(Error::ServerError(status));
+            }
+            if status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(Error::UnexpectedStatus(status));
+            }
+            // every request of the family backs off together
+            backoff.pause(policy.delay(attempt));
+            if attempt >= policy.max_retries {
+                return Err(Error::RateLimitExceeded {
+                    attempts: attempt + 1,
+                });
+            }
+            attempt += 1;
+        }
+    }
+    /// Data this build of the crate can fetch
+    pub fn capabilities(&self) -> &[Capability] {
+        CAPABILITIES
+    }
+    /// Measure round-trip latency to the realtime endpoint
+    pub async fn ping_latency(&self) -> Result<Duration, Error> {
+        self.ping(realtime::ENDPOINT).await
+    }
+    /// Measure round-trip latency to each TWSE endpoint separately
+    pub async fn ping_latency_all(&self) -> Result<Latency, Error> {
+        Ok(Latency {
+            realtime: self.ping(realtime::ENDPOINT).await?,
+            history: self.ping(history::ENDPOINT).await?,
+            list: self.ping(list::ENDPOINT).await?,
+        })
+    }
+    /// Time a `HEAD` request, paced and bounded the same way as [`Client::get`]
+    async fn ping(&self, endpoint: &str) -> Result<Duration, Error> {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        let _permit = match &self.concurrency {
+            Some(x) => x.acquire().await.ok(),
+            None => None,
+        };
+        let start = Instant::now();
+        let status = self
+            .http
+            .head(self.endpoint(endpoint))
+            .send()
+            .await?
+            .status();
+        let elapsed = start.elapsed();
+        if status.is_success() {
+            Ok(elapsed)
+        } else if status.is_server_error() {
+            Err(Error::ServerError(status))
+        } else {
+            Err(Error::UnexpectedStatus(status))
+        }
+    }
+}
+
+/// Kind of data the crate can fetch, see [`Client::capabilities`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    /// realtime quote, see [`realtime`]
+    RealTime,
+    /// odd-lot realtime quote, see [`realtime::RealTime::fetch_odd_lot`]
+    OddLot,
+    /// monthly trading history of a stock, see [`history`]
+    History,
+    /// daily report of the whole market, see [`history::History::fetch_all_in`]
+    WholeMarket,
+    /// daily category indices, see [`history::History::fetch_category_indices`]
+    CategoryIndex,
+    /// currently tradable stocks, see [`list`]
+    List,
+    /// basic information of listed companies, see [`company`]
+    Company,
+    /// daily net of institutional investors, see [`institutional`]
+    Institutional,
+    /// daily short sale balance, see [`short_sale`]
+    ShortSale,
+    /// daily margin trading balance, see [`margin`]
+    Margin,
+    /// daily foreign investor shareholding, see [`foreign`]
+    Foreign,
+    /// daily closing TAIEX, see [`index`]
+    MarketIndex,
+    /// ex-rights and ex-dividend, see [`dividend`]
+    Dividend,
+}
+
+static CAPABILITIES: &[Capability] = &[
+    Capability::RealTime,
+    Capability::OddLot,
+    Capability::History,
+    Capability::WholeMarket,
+    Capability::CategoryIndex,
+    Capability::List,
+    Capability::Company,
+    Capability::Institutional,
+    Capability::ShortSale,
+    Capability::Margin,
+    Capability::Foreign,
+    Capability::MarketIndex,
+    Capability::Dividend,
+];
+
+/// Round-trip latency of each TWSE endpoint
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Latency {
+    pub realtime: Duration,
+    pub history: Duration,
+    pub list: Duration,
 }
 
 // if not TLS feature enabled, compile error
@@ -101,3 +890,124 @@ impl Client {
     feature = "rustls-tls"
 )))]
 compile_error!("TLS feature is not enabled");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roc_year() {
+        assert_eq!(roc_to_gregorian_year(0), 1911);
+        assert_eq!(roc_to_gregorian_year(110), 2021);
+        assert_eq!(roc_to_gregorian_year(113), 2024);
+    }
+    #[test]
+    fn json_number() {
+        for raw in ["0", "42", "-7", "1.5", "18446744073709551615", "1e3"] {
+            let x: serde_json::Number = serde_json::from_str(raw).unwrap();
+            assert_eq!(u64::from_json_number(&x), x.as_u64());
+            assert_eq!(i64::from_json_number(&x), x.as_i64());
+            assert_eq!(f64::from_json_number(&x), x.as_f64());
+        }
+    }
+    #[test]
+    fn incompatible_context() {
+        let err = Error::incompatible().with_body(&[b'x'; 300]);
+        assert!(matches!(&err, Error::IncompatibleApi { context } if context.len() == CONTEXT_LEN));
+        // context is kept once attached
+        let err = err.with_body(b"other");
+        assert!(matches!(&err, Error::IncompatibleApi { context } if context.starts_with('x')));
+        assert_eq!(
+            Error::incompatible().to_string(),
+            "incompatible upstream api"
+        );
+        assert!(matches!(
+            Error::MarketClosed.with_body(b"x"),
+            Error::MarketClosed
+        ));
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_stock_kind() {
+        for (kind, raw) in [
+            (StockKind::Live, r#""TSE""#),
+            (StockKind::OverTheCounter, r#""OTC""#),
+        ] {
+            assert_eq!(serde_json::to_string(&kind).unwrap(), raw);
+            assert_eq!(serde_json::from_str::<StockKind>(raw).unwrap(), kind);
+        }
+        // written by earlier versions
+        assert_eq!(
+            serde_json::from_str::<StockKind>(r#""OverTheCounter""#).unwrap(),
+            StockKind::OverTheCounter
+        );
+    }
+    #[test]
+    fn validate_code() {
+        for code in [50, 2330, 6208, 999_999] {
+            assert!(Stock::live(code).validate().is_ok());
+        }
+        for code in [0, 1_000_000, 99_999_999] {
+            assert!(matches!(
+                Stock::live(code).validate(),
+                Err(Error::InvalidStockCode(x)) if x == code
+            ));
+        }
+    }
+    #[test]
+    fn stock_range() {
+        let stocks = Stock::range(StockKind::OverTheCounter, 2300..=2302).collect::<Vec<_>>();
+        assert_eq!(stocks.len(), 3);
+        assert_eq!(stocks[2].code, 2302);
+        assert!(stocks.iter().all(|x| x.kind == StockKind::OverTheCounter));
+    }
+    #[tokio::test]
+    async fn connect_error() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = Client::new().with_base_url(format!("http://{}", addr));
+        let err = client.company().fetch_all().await.unwrap_err();
+        assert!(matches!(err, Error::Connect(_)));
+        assert!(err.is_retryable());
+    }
+    #[test]
+    fn twse_channel() {
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        assert_eq!(stock.to_twse_channel(), "tse_2330.tw");
+        assert_eq!("tse_2330.tw".parse::<Stock>().unwrap(), stock);
+        for s in [
+            "",
+            "tse_2330",
+            "abc_2330.tw",
+            "tse_.tw",
+            "tse_+1.tw",
+            "tse_2330.tw_odd",
+        ] {
+            assert!(matches!(s.parse::<Stock>(), Err(Error::InvalidChannel(_))));
+        }
+    }
+    #[test]
+    fn csv_guard() {
+        assert_eq!(strip_csv_guard("=\"2,330\""), "2,330");
+        assert_eq!(strip_csv_guard("=\"0050"), "=\"0050");
+        assert_eq!(strip_csv_guard("2330"), "2330");
+        assert_eq!(
+            parse_roc_date("=\"110/01/04\""),
+            chrono::NaiveDate::from_ymd_opt(2021, 1, 4)
+        );
+    }
+    #[test]
+    fn roc_date() {
+        assert_eq!(
+            parse_roc_date("110/01/04"),
+            chrono::NaiveDate::from_ymd_opt(2021, 1, 4)
+        );
+        assert_eq!(parse_roc_date("110/02/29"), None);
+        assert_eq!(parse_roc_date("110/01/04/01"), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Delisted (終止上市) companies and the list as of a past date
+
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use super::{List, StockInfo};
+use crate::table::{self, Report};
+use crate::{parse_roc_date, Error, Family, MediaType, Stock, StockKind};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/rwd/zh/company/suspendListing";
+/// column headers of `suspendListing` this module reads
+pub(crate) const FIELDS: [&str; 3] = ["終止上市日期", "公司名稱", "上市編號"];
+
+/// A company no longer listed on TWSE
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct DelistedStock {
+    /// Stock identifier
+    pub id: Stock,
+    /// chinese name
+    pub name: String,
+    /// first day the stock is no longer traded
+    pub delisted_date: NaiveDate,
+}
+
+fn parse_delisted(body: &[u8]) -> Result<Vec<DelistedStock>, Error> {
+    let report: Report = table::parse_json(body)?;
+    let [date, name, code] = report.columns(FIELDS)?;
+    let cell = |row: &[Value], i: usize| -> Result<String, Error> {
+        match row.get(i) {
+            Some(Value::String(x)) => Ok(x.trim().to_string()),
+            Some(Value::Number(x)) => Ok(x.to_string()),
+            _ => Err(Error::incompatible()),
+        }
+    };
+    report
+        .data
+        .iter()
+        .map(|row| {
+            Ok(DelistedStock {
+                id: Stock {
+                    kind: StockKind::Live,
+                    code: table::cell(row, code)?,
+                },
+                name: cell(row, name)?,
+                delisted_date: parse_roc_date(&cell(row, date)?).ok_or(Error::incompatible())?,
+            })
+        })
+        .collect()
+}
+
+/// Reconstruct the list as of `date` from the current list and delisted stocks
+///
+/// Stock listed after `date` is dropped and stock delisted after `date` is added back.
+fn as_of(current: Vec<StockInfo>, delisted: Vec<DelistedStock>, date: NaiveDate) -> Vec<StockInfo> {
+    let mut result = current
+        .into_iter()
+        .filter(|x| x.release_date <= date)
+        .collect::<Vec<_>>();
+    let delisted = delisted
+        .into_iter()
+        .filter(|x| x.delisted_date > date)
+        .filter(|x| result.iter().all(|y| y.id != x.id))
+        .map(|x| StockInfo {
+            id: x.id,
+            abbr: x.name,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    result.extend(delisted);
+    result
+}
+
+impl List<'_> {
+    /// Fetch companies delisted from TWSE
+    pub async fn fetch_delisted(&self) -> Result<Vec<DelistedStock>, Error> {
+        let body = self
+            .0
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[("response", "json")],
+                MediaType::Json,
+            )
+            .await?;
+        parse_delisted(&body).map_err(|err| err.with_body(&body))
+    }
+    /// Fetch the list of stocks tradable on `date`, to avoid survivorship bias in backtesting
+    ///
+    /// TWSE doesn't publish historical list, so it's reconstructed from the current list
+    /// (dropping stock with `release_date` after `date`) and [`List::fetch_delisted`]
+    /// (adding back stock delisted after `date`). Limitations:
+    /// - delisted stock has full name as `abbr`, default `release_date` and `industry`,
+    ///   so it's kept regardless of when it was listed
+    /// - delisting is only available for [`StockKind::Live`], OTC list is the current list
+    ///   filtered by `release_date`
+    /// - stock moved between markets or relisted under the same code has its latest
+    ///   `release_date` only
+    pub async fn fetch_as_of(
+        &self,
+        date: NaiveDate,
+        kind: StockKind,
+    ) -> Result<Vec<StockInfo>, Error> {
+        let current = self.fetch(kind.clone()).await?;
+        let delisted = match kind {
+            StockKind::Live => self.fetch_delisted().await?,
+            StockKind::OverTheCounter => Vec::new(),
+        };
+        Ok(as_of(current, delisted, date))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let data = parse_delisted(include_bytes!("../../test/suspendListing.json")).unwrap();
+        assert_eq!(data.len(), 4);
+        assert_eq!(data[1].id.code, 2311);
+        assert_eq!(data[1].delisted_date, date(2018, 4, 30));
+    }
+    #[test]
+    fn as_of() {
+        let stock = |code, release_date| StockInfo {
+            id: Stock {
+                kind: StockKind::Live,
+                code,
+            },
+            release_date,
+            ..Default::default()
+        };
+        let current = vec![
+            stock(2330, date(1994, 9, 5)),
+            stock(6669, date(2019, 3, 27)),
+        ];
+        let delisted = parse_delisted(include_bytes!("../../test/suspendListing.json")).unwrap();
+        let list = super::as_of(current, delisted, date(2018, 1, 2));
+        let codes = list.iter().map(|x| x.id.code).collect::<Vec<_>>();
+        assert_eq!(codes, [2330, 3576, 2311]);
+    }
+}
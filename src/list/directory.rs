@@ -0,0 +1,80 @@
+//! Stock list indexed by stock for synchronous lookup
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use super::{Industry, List, StockInfo};
+use crate::{Error, Stock, StockKind};
+
+/// Stock list keyed by [`Stock`], to enrich realtime or history data with metadata
+///
+/// Unlike [`super::ReferenceData`], it's a snapshot never refreshed, and lookup is
+/// synchronous.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StockDirectory(HashMap<Stock, StockInfo>);
+
+impl From<Vec<StockInfo>> for StockDirectory {
+    fn from(value: Vec<StockInfo>) -> Self {
+        Self(value.into_iter().map(|x| (x.id.clone(), x)).collect())
+    }
+}
+
+impl StockDirectory {
+    /// Stock information, `None` if the stock is not in the list
+    pub fn get(&self, stock: &Stock) -> Option<&StockInfo> {
+        self.0.get(stock)
+    }
+    /// Chinese name abbreviation
+    pub fn abbr_of(&self, stock: &Stock) -> Option<&str> {
+        self.get(stock).map(|x| x.abbr.as_str())
+    }
+    /// Industry category
+    pub fn industry_of(&self, stock: &Stock) -> Option<&Industry> {
+        self.get(stock).map(|x| &x.industry)
+    }
+    /// Day the stock was first traded
+    pub fn release_date_of(&self, stock: &Stock) -> Option<NaiveDate> {
+        self.get(stock).map(|x| x.release_date)
+    }
+    /// Number of stocks in the directory
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Whether the directory is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl List<'_> {
+    /// Fetch the list of currently tradable stocks of `kind` as a [`StockDirectory`]
+    pub async fn directory(&self, kind: StockKind) -> Result<StockDirectory, Error> {
+        Ok(self.fetch(kind).await?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn directory() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/isin/C_public.jsp",
+            200,
+            &include_bytes!("../../test/C_public.jsp.html.small")[..],
+        );
+        let client = server.client();
+        let directory = client.list().directory(StockKind::Live).await.unwrap();
+        let stock = Stock::live(2506);
+        assert_eq!(directory.abbr_of(&stock), Some("太設"));
+        assert_eq!(directory.industry_of(&stock), Some(&Industry::Construction));
+        assert_eq!(
+            directory.release_date_of(&stock),
+            NaiveDate::from_ymd_opt(1980, 2, 2)
+        );
+        assert_eq!(directory.get(&Stock::live(2330)), None);
+    }
+}
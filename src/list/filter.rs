@@ -0,0 +1,116 @@
+//! Fluent client-side screening of the stock list
+
+use std::ops::RangeBounds;
+
+use chrono::NaiveDate;
+
+use super::{Industry, StockInfo};
+use crate::StockKind;
+
+/// Start screening a fetched stock list, see [`Filter`]
+pub trait FilterExt {
+    /// Screen the list with chained predicates, all of them must hold
+    ///
+    /// ```rust,no_run
+    /// use chrono::NaiveDate;
+    /// use twstock::{list::*, *};
+    ///
+    /// async fn screen() {
+    ///     let client = Client::new();
+    ///     let stocks = client
+    ///         .list()
+    ///         .fetch(StockKind::Live)
+    ///         .await
+    ///         .unwrap()
+    ///         .filter()
+    ///         .industry(Industry::Semiconductor)
+    ///         .listed_before(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap())
+    ///         .code_range(2000..3000)
+    ///         .collect();
+    /// }
+    /// ```
+    fn filter(self) -> Filter;
+}
+
+impl FilterExt for Vec<StockInfo> {
+    fn filter(self) -> Filter {
+        Filter(self)
+    }
+}
+
+/// Stock list narrowed by each predicate applied
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Filter(Vec<StockInfo>);
+
+impl Filter {
+    /// Keep stocks satisfying `predicate`, for anything not covered by other methods
+    pub fn matching(mut self, predicate: impl FnMut(&StockInfo) -> bool) -> Self {
+        self.0.retain(predicate);
+        self
+    }
+    /// Keep stocks of `industry`
+    pub fn industry(self, industry: Industry) -> Self {
+        self.matching(|x| x.industry == industry)
+    }
+    /// Keep stocks of market `kind`
+    pub fn kind(self, kind: StockKind) -> Self {
+        self.matching(|x| x.id.kind == kind)
+    }
+    /// Keep stocks first traded before `date` (exclusive)
+    pub fn listed_before(self, date: NaiveDate) -> Self {
+        self.matching(|x| x.release_date < date)
+    }
+    /// Keep stocks first traded on or after `date`
+    pub fn listed_since(self, date: NaiveDate) -> Self {
+        self.matching(|x| x.release_date >= date)
+    }
+    /// Keep stocks with code in `codes`
+    pub fn code_range(self, codes: impl RangeBounds<u32>) -> Self {
+        self.matching(|x| codes.contains(&x.id.code))
+    }
+    /// Stocks passing every predicate, in the original order
+    pub fn collect(self) -> Vec<StockInfo> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stock;
+
+    fn info(code: u32, year: i32, industry: Industry) -> StockInfo {
+        StockInfo {
+            id: Stock {
+                kind: StockKind::Live,
+                code,
+            },
+            abbr: code.to_string(),
+            release_date: NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+            industry,
+        }
+    }
+
+    #[test]
+    fn chained() {
+        let list = vec![
+            info(2330, 1994, Industry::Semiconductor),
+            info(2303, 1985, Industry::Semiconductor),
+            info(6770, 2021, Industry::Semiconductor),
+            info(1101, 1962, Industry::Cement),
+        ];
+        let result = list
+            .clone()
+            .filter()
+            .industry(Industry::Semiconductor)
+            .listed_before(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap())
+            .code_range(2000..3000)
+            .collect();
+        assert_eq!(result, list[..2]);
+        let result = list
+            .filter()
+            .listed_since(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap())
+            .collect();
+        assert_eq!(result[0].id.code, 6770);
+    }
+}
@@ -3,18 +3,31 @@
 //! This module is technically not an API binding,
 //! it's a http client with custom parser to list currently tradable stocks.
 
-mod parser;
+mod delisted;
+mod directory;
+mod filter;
+pub(crate) mod parser;
+mod reference;
 
-use std::fmt::Display;
+pub use delisted::DelistedStock;
+pub use directory::StockDirectory;
+pub use filter::{Filter, FilterExt};
+pub use reference::ReferenceData;
+
+use std::{collections::HashMap, fmt::Display};
+#[cfg(feature = "serde")]
+use std::{path::Path, time::Duration};
 
 use chrono::NaiveDate;
+use itertools::Itertools;
 use parser::RawContent;
 
-use crate::{Client, Error, Stock, StockKind};
+use crate::{Client, Error, Family, MediaType, Stock, StockKind, Validator};
 
-static ENDPOINT: &str = "https://isin.twse.com.tw/isin/C_public.jsp";
+pub(crate) static ENDPOINT: &str = "https://isin.twse.com.tw/isin/C_public.jsp";
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Industry category
 pub enum Industry {
     /// consumer electronics
@@ -26,6 +39,54 @@ pub enum Industry {
     Steel,
     Semiconductor,
     Construction,
+    /// electrical and cable
+    ElectricCable,
+    Plastics,
+    Textiles,
+    Chemical,
+    /// biotechnology and medical care
+    Biotech,
+    /// chemical, biotechnology and medical care, merged category of older reports
+    ChemicalBiotech,
+    /// glass and ceramics
+    Glass,
+    /// paper and pulp
+    Paper,
+    Rubber,
+    /// computer and peripheral equipment
+    Computer,
+    Optoelectronic,
+    /// communications and internet
+    Communications,
+    /// electronic products distribution
+    ElectronicDistribution,
+    InformationService,
+    OtherElectronic,
+    /// shipping and transportation
+    Shipping,
+    Tourism,
+    /// tourism and hospitality, renamed from [`Industry::Tourism`]
+    TourismHospitality,
+    /// financial and insurance
+    Financial,
+    /// trading and consumers' goods
+    Trading,
+    /// oil, gas and electricity
+    OilGasElectricity,
+    /// green energy and environmental services
+    GreenEnergy,
+    /// digital and cloud services
+    DigitalCloud,
+    /// sports and leisure
+    SportsLeisure,
+    Household,
+    /// cultural and creative
+    Cultural,
+    /// agricultural science and technology
+    Agricultural,
+    /// official catch-all category (其他業), unlike [`Industry::Other`]
+    Others,
+    /// category this crate doesn't know yet, with its chinese name
     Other(String),
 }
 
@@ -37,28 +98,122 @@ impl Default for Industry {
 
 impl Display for Industry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{:?}",
-            match self {
-                Industry::Electronic => "電子零組件業",
-                Industry::Cement => "水泥工業",
-                Industry::Food => "食品工業",
-                Industry::Car => "汽車工業",
-                Industry::Motor => "電機機械",
-                Industry::Steel => "鋼鐵工業",
-                Industry::Semiconductor => "半導體業",
-                Industry::Construction => "建材營造業",
-                Industry::Other(s) => s,
-            }
-        )
+        f.write_str(self.as_chinese())
+    }
+}
+
+/// Official TWSE/TPEx industry names and their english translation
+const ENGLISH_NAMES: [(&str, &str); 37] = [
+    ("水泥工業", "Cement"),
+    ("食品工業", "Foods"),
+    ("塑膠工業", "Plastics"),
+    ("紡織纖維", "Textiles"),
+    ("電機機械", "Electric Machinery"),
+    ("電器電纜", "Electrical and Cable"),
+    ("化學工業", "Chemical"),
+    ("生技醫療業", "Biotechnology and Medical Care"),
+    ("化學生技醫療", "Chemical, Biotechnology and Medical Care"),
+    ("玻璃陶瓷", "Glass and Ceramics"),
+    ("造紙工業", "Paper and Pulp"),
+    ("鋼鐵工業", "Iron and Steel"),
+    ("橡膠工業", "Rubber"),
+    ("汽車工業", "Automobile"),
+    ("電子工業", "Electronic"),
+    ("半導體業", "Semiconductor"),
+    ("電腦及週邊設備業", "Computer and Peripheral Equipment"),
+    ("光電業", "Optoelectronic"),
+    ("通信網路業", "Communications and Internet"),
+    ("電子零組件業", "Electronic Parts/Components"),
+    ("電子通路業", "Electronic Products Distribution"),
+    ("資訊服務業", "Information Service"),
+    ("其他電子業", "Other Electronic"),
+    ("建材營造業", "Building Material and Construction"),
+    ("航運業", "Shipping and Transportation"),
+    ("觀光事業", "Tourism"),
+    ("觀光餐旅", "Tourism and Hospitality"),
+    ("金融保險業", "Financial and Insurance"),
+    ("貿易百貨業", "Trading and Consumers' Goods"),
+    ("油電燃氣業", "Oil, Gas and Electricity"),
+    ("綠能環保", "Green Energy and Environmental Services"),
+    ("數位雲端", "Digital and Cloud Services"),
+    ("運動休閒", "Sports and Leisure"),
+    ("居家生活", "Household"),
+    ("文化創意業", "Cultural and Creative"),
+    ("農業科技業", "Agricultural Science and Technology"),
+    ("其他業", "Others"),
+];
+
+impl Industry {
+    /// Canonical chinese name of the industry, [`Industry::from`] turns it back
+    ///
+    /// Unknown industry in [`Industry::Other`] is returned as is
+    pub fn as_chinese(&self) -> &str {
+        match self {
+            Industry::Electronic => "電子零組件業",
+            Industry::Cement => "水泥工業",
+            Industry::Food => "食品工業",
+            Industry::Car => "汽車工業",
+            Industry::Motor => "電機機械",
+            Industry::Steel => "鋼鐵工業",
+            Industry::Semiconductor => "半導體業",
+            Industry::Construction => "建材營造業",
+            Industry::ElectricCable => "電器電纜",
+            Industry::Plastics => "塑膠工業",
+            Industry::Textiles => "紡織纖維",
+            Industry::Chemical => "化學工業",
+            Industry::Biotech => "生技醫療業",
+            Industry::ChemicalBiotech => "化學生技醫療",
+            Industry::Glass => "玻璃陶瓷",
+            Industry::Paper => "造紙工業",
+            Industry::Rubber => "橡膠工業",
+            Industry::Computer => "電腦及週邊設備業",
+            Industry::Optoelectronic => "光電業",
+            Industry::Communications => "通信網路業",
+            Industry::ElectronicDistribution => "電子通路業",
+            Industry::InformationService => "資訊服務業",
+            Industry::OtherElectronic => "其他電子業",
+            Industry::Shipping => "航運業",
+            Industry::Tourism => "觀光事業",
+            Industry::TourismHospitality => "觀光餐旅",
+            Industry::Financial => "金融保險業",
+            Industry::Trading => "貿易百貨業",
+            Industry::OilGasElectricity => "油電燃氣業",
+            Industry::GreenEnergy => "綠能環保",
+            Industry::DigitalCloud => "數位雲端",
+            Industry::SportsLeisure => "運動休閒",
+            Industry::Household => "居家生活",
+            Industry::Cultural => "文化創意業",
+            Industry::Agricultural => "農業科技業",
+            Industry::Others => "其他業",
+            Industry::Other(s) => s,
+        }
+    }
+    /// English name of the industry
+    ///
+    /// Unknown industry in [`Industry::Other`] is returned as is
+    pub fn english_name(&self) -> &str {
+        let chinese = self.as_chinese();
+        ENGLISH_NAMES
+            .iter()
+            .find(|(x, _)| *x == chinese)
+            .map_or(chinese, |(_, english)| english)
+    }
+    /// Whether the industry is one of the official categories
+    ///
+    /// Empty [`Industry::Other`] (e.g. ETF) is known as no category
+    pub fn is_known(&self) -> bool {
+        match self {
+            Industry::Other(s) => s.is_empty() || ENGLISH_NAMES.iter().any(|(x, _)| x == s),
+            _ => true,
+        }
     }
 }
 
 impl From<&str> for Industry {
     fn from(value: &str) -> Self {
         match value {
-            "電子零組件業" | "電子通路" | "電器電纜" => Industry::Electronic,
+            "電子零組件業" | "電子通路" => Industry::Electronic,
+            "電器電纜" => Industry::ElectricCable,
             "水泥工業" => Industry::Cement,
             "食品工業" => Industry::Food,
             "汽車工業" => Industry::Car,
@@ -66,7 +221,33 @@ impl From<&str> for Industry {
             "鋼鐵工業" => Industry::Steel,
             "半導體業" => Industry::Semiconductor,
             "建材營造業" => Industry::Construction,
-            // FIXME: add more industries
+            "塑膠工業" => Industry::Plastics,
+            "紡織纖維" => Industry::Textiles,
+            "化學工業" => Industry::Chemical,
+            "生技醫療業" => Industry::Biotech,
+            "化學生技醫療" => Industry::ChemicalBiotech,
+            "玻璃陶瓷" => Industry::Glass,
+            "造紙工業" => Industry::Paper,
+            "橡膠工業" => Industry::Rubber,
+            "電腦及週邊設備業" => Industry::Computer,
+            "光電業" => Industry::Optoelectronic,
+            "通信網路業" => Industry::Communications,
+            "電子通路業" => Industry::ElectronicDistribution,
+            "資訊服務業" => Industry::InformationService,
+            "其他電子業" => Industry::OtherElectronic,
+            "航運業" => Industry::Shipping,
+            "觀光事業" => Industry::Tourism,
+            "觀光餐旅" => Industry::TourismHospitality,
+            "金融保險業" => Industry::Financial,
+            "貿易百貨業" => Industry::Trading,
+            "油電燃氣業" => Industry::OilGasElectricity,
+            "綠能環保" => Industry::GreenEnergy,
+            "數位雲端" => Industry::DigitalCloud,
+            "運動休閒" => Industry::SportsLeisure,
+            "居家生活" => Industry::Household,
+            "文化創意業" => Industry::Cultural,
+            "農業科技業" => Industry::Agricultural,
+            "其他業" => Industry::Others,
             _ => Industry::Other(value.to_string()),
         }
     }
@@ -74,6 +255,7 @@ impl From<&str> for Industry {
 
 /// Stock information
 #[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StockInfo {
     /// Stock identifier
     pub id: Stock,
@@ -85,6 +267,39 @@ pub struct StockInfo {
     pub industry: Industry,
 }
 
+/// Bare codes tagged with their market, see [`Client::resolve_codes`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ResolvedCodes {
+    /// code listed on exactly one market
+    pub stocks: HashMap<u32, Stock>,
+    /// code on both markets, left out of `stocks`
+    pub ambiguous: Vec<u32>,
+    /// code on neither market, e.g. delisted or a typo
+    pub unknown: Vec<u32>,
+}
+
+/// list in `path` if it was written within `ttl`
+#[cfg(feature = "serde")]
+fn read_cache(path: &Path, ttl: Duration) -> Option<Vec<StockInfo>> {
+    let age = std::fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .elapsed()
+        .ok()?;
+    if age > ttl {
+        return None;
+    }
+    let body = std::fs::read(path).ok()?;
+    match serde_json::from_slice(&body) {
+        Ok(x) => Some(x),
+        Err(err) => {
+            log::warn!("corrupt stock list cache {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
 /// newtype wrapper for the [`Client`] facilitating list api
 pub struct List<'a>(&'a Client);
 
@@ -93,6 +308,43 @@ impl Client {
     pub fn list(&self) -> List<'_> {
         List(self)
     }
+    /// Confirm the stock is currently tradable and get its information
+    ///
+    /// Fetch the list of the stock's market, so it's authoritative unlike guessing
+    /// [`StockKind`] from the code. Use [`ReferenceData`] to validate many stocks.
+    pub async fn validate(&self, stock: &Stock) -> Result<StockInfo, Error> {
+        self.list()
+            .fetch(stock.kind.clone())
+            .await?
+            .into_iter()
+            .find(|x| x.id == *stock)
+            .ok_or(Error::UnknownStock(stock.code))
+    }
+    /// Tag bare `codes` (e.g. from an external source) with their market
+    ///
+    /// Fetch both lists once regardless of how many codes, duplicated code is resolved once.
+    pub async fn resolve_codes(&self, codes: &[u32]) -> Result<ResolvedCodes, Error> {
+        let mut markets: HashMap<u32, Vec<Stock>> = HashMap::new();
+        for kind in [StockKind::Live, StockKind::OverTheCounter] {
+            for info in self.list().fetch(kind).await? {
+                let stocks = markets.entry(info.id.code).or_default();
+                if !stocks.contains(&info.id) {
+                    stocks.push(info.id);
+                }
+            }
+        }
+        let mut result = ResolvedCodes::default();
+        for code in codes.iter().copied().unique() {
+            match markets.remove(&code).as_deref() {
+                Some([stock]) => {
+                    result.stocks.insert(code, stock.clone());
+                }
+                Some([_, _, ..]) => result.ambiguous.push(code),
+                _ => result.unknown.push(code),
+            }
+        }
+        Ok(result)
+    }
 }
 
 impl List<'_> {
@@ -100,21 +352,87 @@ impl List<'_> {
     pub async fn fetch(&self, kind: StockKind) -> Result<Vec<StockInfo>, Error> {
         let raw = self.fetch_raw(kind).await?;
         let parser = RawContent(&raw);
-        parser.parse()
+        parser
+            .parse(self.0.parse_mode, self.0.strict_industry)
+            .map_err(|err| err.with_body(&raw))
+    }
+    /// Same as [`List::fetch`], cached in `cache_path` for `ttl`
+    ///
+    /// The cache file is read if it was written within `ttl`, otherwise the list is fetched
+    /// and the file rewritten. Unreadable or corrupt cache is fetched again instead of
+    /// failing.
+    #[cfg(feature = "serde")]
+    pub async fn fetch_cached(
+        &self,
+        kind: StockKind,
+        cache_path: &Path,
+        ttl: Duration,
+    ) -> Result<Vec<StockInfo>, Error> {
+        if let Some(x) = read_cache(cache_path, ttl) {
+            return Ok(x);
+        }
+        let list = self.fetch(kind).await?;
+        // written aside then renamed, so a reader never sees a partial file
+        let temp = cache_path.with_extension("tmp");
+        let body = serde_json::to_vec(&list).map_err(std::io::Error::from)?;
+        std::fs::write(&temp, body)?;
+        std::fs::rename(&temp, cache_path)?;
+        Ok(list)
+    }
+    /// Fetch the list only if it has changed since `validator` was returned
+    ///
+    /// `None` if upstream answered `304 Not Modified`, keep using the previous list. Pass
+    /// [`Validator::default`] for the first fetch, then the one returned with the list.
+    pub async fn fetch_if_modified(
+        &self,
+        kind: StockKind,
+        validator: &Validator,
+    ) -> Result<Option<(Vec<StockInfo>, Validator)>, Error> {
+        let Some((raw, validator)) = self
+            .0
+            .get_if_modified(
+                Family::Report,
+                ENDPOINT,
+                &[("strMode", &(kind as u8).to_string())],
+                MediaType::Html,
+                Some(validator),
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        let list = RawContent(&raw)
+            .parse(self.0.parse_mode, self.0.strict_industry)
+            .map_err(|err| err.with_body(&raw))?;
+        Ok(Some((list, validator)))
+    }
+    /// Look up the market of a bare `code`, listed stocks first
+    ///
+    /// Fetch up to both lists, [`Error::UnknownStock`] if neither has the code.
+    pub async fn resolve(&self, code: u32) -> Result<Stock, Error> {
+        for kind in [StockKind::Live, StockKind::OverTheCounter] {
+            if let Some(x) = self
+                .fetch(kind)
+                .await?
+                .into_iter()
+                .find(|x| x.id.code == code)
+            {
+                return Ok(x.id);
+            }
+        }
+        Err(Error::UnknownStock(code))
     }
     async fn fetch_raw(&self, kind: StockKind) -> Result<Vec<u8>, Error> {
-        let response = self
+        let body = self
             .0
-             .0
-            .get(ENDPOINT)
-            .query(&[("strMode", (kind as u8).to_string())])
-            .send()
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[("strMode", &(kind as u8).to_string())],
+                MediaType::Html,
+            )
             .await?;
-        if response.status().is_success() {
-            Ok(response.bytes().await?.to_vec())
-        } else {
-            Err(Error::RateLimitExceeded)
-        }
+        Ok(body)
     }
 }
 
@@ -123,6 +441,182 @@ mod test {
     use super::*;
     use crate::StockKind;
 
+    #[test]
+    fn english_name() {
+        assert_eq!(Industry::Semiconductor.english_name(), "Semiconductor");
+        assert_eq!(
+            Industry::from("航運業").english_name(),
+            "Shipping and Transportation"
+        );
+        assert_eq!(Industry::from("未知產業").english_name(), "未知產業");
+    }
+    #[test]
+    fn as_chinese() {
+        for industry in [
+            Industry::Electronic,
+            Industry::Cement,
+            Industry::Food,
+            Industry::Car,
+            Industry::Motor,
+            Industry::Steel,
+            Industry::Semiconductor,
+            Industry::Construction,
+            Industry::Other("未知產業".to_string()),
+        ] {
+            assert_eq!(Industry::from(industry.as_chinese()), industry);
+        }
+        assert_eq!(Industry::Cement.to_string(), "水泥工業");
+    }
+    #[test]
+    fn official_names() {
+        for (chinese, english) in ENGLISH_NAMES {
+            let industry = Industry::from(chinese);
+            assert_eq!(industry.english_name(), english);
+            assert!(industry.is_known());
+        }
+        assert_eq!(Industry::from("金融保險業"), Industry::Financial);
+        assert_eq!(Industry::from("航運業"), Industry::Shipping);
+        assert_eq!(Industry::from("觀光事業"), Industry::Tourism);
+        assert_eq!(Industry::from("生技醫療業"), Industry::Biotech);
+        assert_eq!(Industry::from("塑膠工業"), Industry::Plastics);
+        assert_eq!(Industry::from("紡織纖維"), Industry::Textiles);
+        assert_eq!(Industry::from("玻璃陶瓷"), Industry::Glass);
+        assert_eq!(Industry::Financial.as_chinese(), "金融保險業");
+    }
+    #[test]
+    fn is_known() {
+        assert!(Industry::Semiconductor.is_known());
+        assert!(Industry::from("航運業").is_known());
+        assert!(Industry::from("").is_known());
+        assert!(!Industry::from("未知產業").is_known());
+    }
+
+    #[tokio::test]
+    async fn validate() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/isin/C_public.jsp",
+            200,
+            &include_bytes!("../../test/C_public.jsp.html.small")[..],
+        );
+        let client = server.client();
+        let list = client.list().fetch(StockKind::Live).await.unwrap();
+        let info = client.validate(&list[0].id).await.unwrap();
+        assert_eq!(info, list[0]);
+        let unknown = Stock {
+            kind: StockKind::Live,
+            code: 9999,
+        };
+        assert!(matches!(
+            client.validate(&unknown).await,
+            Err(Error::UnknownStock(x)) if x == unknown.code
+        ));
+    }
+    #[tokio::test]
+    async fn resolve() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/isin/C_public.jsp",
+            200,
+            &include_bytes!("../../test/C_public.jsp.html.small")[..],
+        );
+        let client = server.client();
+        let list = client.list().fetch(StockKind::Live).await.unwrap();
+        let code = list[0].id.code;
+        assert_eq!(
+            client.list().resolve(code).await.unwrap(),
+            Stock::live(code)
+        );
+        assert!(matches!(
+            client.list().resolve(9999).await,
+            Err(Error::UnknownStock(9999))
+        ));
+        // both lists are searched before giving up
+        assert_eq!(server.hits("/isin/C_public.jsp"), 4);
+    }
+    #[tokio::test]
+    async fn fetch_if_modified() {
+        let server = crate::mock::MockServer::start();
+        server
+            .enqueue(
+                "/isin/C_public.jsp",
+                200,
+                &include_bytes!("../../test/C_public.jsp.html.small")[..],
+            )
+            .enqueue("/isin/C_public.jsp", 304, "");
+        let client = server.client();
+        let list = client.list();
+        let (data, validator) = list
+            .fetch_if_modified(StockKind::Live, &Validator::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.len(), 3);
+        assert!(list
+            .fetch_if_modified(StockKind::Live, &validator)
+            .await
+            .unwrap()
+            .is_none());
+        // without conditional request, 304 is not a success
+        assert!(list.fetch(StockKind::Live).await.is_err());
+    }
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn fetch_cached() {
+        use std::time::Duration;
+
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/isin/C_public.jsp",
+            200,
+            &include_bytes!("../../test/C_public.jsp.html.small")[..],
+        );
+        let path = std::env::temp_dir().join(format!("twstock-list-{}.json", std::process::id()));
+        std::fs::write(&path, "not json").unwrap();
+        let client = server.client();
+        let list = client.list();
+        let ttl = Duration::from_secs(60);
+        // corrupt cache is fetched again
+        let data = list
+            .fetch_cached(StockKind::Live, &path, ttl)
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 3);
+        let cached = list
+            .fetch_cached(StockKind::Live, &path, ttl)
+            .await
+            .unwrap();
+        assert_eq!(cached, data);
+        assert_eq!(server.hits("/isin/C_public.jsp"), 1);
+        // expired
+        list.fetch_cached(StockKind::Live, &path, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(server.hits("/isin/C_public.jsp"), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+    #[tokio::test]
+    async fn resolve_codes() {
+        let live = include_bytes!("../../test/C_public.jsp.html.small");
+        let (page, _, _) = encoding_rs::BIG5.decode(live);
+        let page = page.replace("2506", "6488").replace("上市", "上櫃");
+        let (otc, _, _) = encoding_rs::BIG5.encode(&page);
+        let server = crate::mock::MockServer::start();
+        server
+            .enqueue("/isin/C_public.jsp", 200, &live[..])
+            .enqueue("/isin/C_public.jsp", 200, otc.into_owned());
+        let result = server
+            .client()
+            .resolve_codes(&[2506, 6488, 2509, 2506, 9999])
+            .await
+            .unwrap();
+        assert_eq!(result.stocks.len(), 2);
+        assert_eq!(result.stocks[&2506], Stock::live(2506));
+        assert_eq!(result.stocks[&6488], Stock::otc(6488));
+        assert_eq!(result.ambiguous, [2509]);
+        assert_eq!(result.unknown, [9999]);
+        assert_eq!(server.hits("/isin/C_public.jsp"), 2);
+    }
     #[tokio::test]
     #[ignore = "contain large amount of data"]
     async fn list() {
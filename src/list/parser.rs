@@ -1,6 +1,6 @@
-use crate::{Error, StockKind};
+use crate::{Error, ParseMode, StockKind};
 use chrono::NaiveDate;
-use std::{borrow::Cow, ffi::CString};
+use std::borrow::Cow;
 use tl::*;
 
 use encoding_rs::BIG5;
@@ -16,6 +16,8 @@ fn skip_on_table(text: &impl AsRef<str>) -> bool {
 
 const FIELD_COUNT: usize = 7;
 const REQUIRED_COUNT: usize = 5;
+/// column headers of `C_public.jsp` this module reads
+pub(crate) const FIELDS: [&str; 4] = ["有價證券代號及名稱", "上市日", "產業別", "市場別"];
 
 enum Column {
     CodeAndabbr,
@@ -40,9 +42,17 @@ impl FieldMapper {
             })
             .step_by(2)
             .collect::<Vec<_>>();
-        let map = map.try_into().map_err(|_| Error::IncompatibleApi)?;
+        let map = map.try_into().map_err(|_| Error::incompatible())?;
         Ok(Self(map))
     }
+    /// section header rows (e.g. ` 股票 `) carry no stock code
+    fn is_section(&self, data: &[impl AsRef<str>; FIELD_COUNT]) -> bool {
+        data.iter()
+            .zip(self.0.iter())
+            .find(|(_, key)| matches!(key, Column::CodeAndabbr))
+            .and_then(|(value, _)| value.as_ref().split_whitespace().next())
+            .is_none_or(|code| !code.chars().any(|c| c.is_ascii_digit()))
+    }
     fn map(&self, data: &[impl AsRef<str>; FIELD_COUNT]) -> Result<StockInfo, Error> {
         let mut result = StockInfo::default();
 
@@ -51,20 +61,19 @@ impl FieldMapper {
             match key {
                 Column::CodeAndabbr => {
                     let segs = value.split_whitespace().collect::<Vec<_>>();
-                    debug_assert!(segs.len() >= 2, "Invalid code and abbr: {}", value);
                     result.id.code = segs
                         .first()
-                        .ok_or(Error::IncompatibleApi)?
+                        .ok_or(Error::incompatible())?
                         .chars()
                         .filter(char::is_ascii_digit)
                         .collect::<String>()
                         .parse()
-                        .map_err(|_| Error::IncompatibleApi)?;
-                    result.abbr = segs.last().ok_or(Error::IncompatibleApi)?.to_string();
+                        .map_err(|_| Error::incompatible())?;
+                    result.abbr = segs.last().ok_or(Error::incompatible())?.to_string();
                 }
                 Column::ReleaseDate => {
                     result.release_date = NaiveDate::parse_from_str(value, "%Y/%m/%d")
-                        .map_err(|_| Error::IncompatibleApi)?;
+                        .map_err(|_| Error::incompatible())?;
                 }
                 Column::Industry => {
                     result.industry = (*value).into();
@@ -87,26 +96,28 @@ pub struct RawContent<'a>(pub &'a [u8]);
 
 impl RawContent<'_> {
     /// parse the raw content to HTML and file table element
-    pub fn parse(self) -> Result<Vec<StockInfo>, Error> {
+    ///
+    /// With `strict_industry`, an unknown industry is [`Error::IncompatibleApi`] regardless
+    /// of `mode`.
+    pub fn parse(self, mode: ParseMode, strict_industry: bool) -> Result<Vec<StockInfo>, Error> {
         let raw_content = big5_to_utf8(self.0.to_vec());
         let dom =
-            parse(&raw_content, ParserOptions::default()).map_err(|_| Error::IncompatibleApi)?;
+            parse(&raw_content, ParserOptions::default()).map_err(|_| Error::incompatible())?;
         let parser = dom.parser();
-        let mut entries = dom.query_selector("tr").ok_or(Error::IncompatibleApi)?;
+        let mut entries = dom.query_selector("tr").ok_or(Error::incompatible())?;
 
         macro_rules! to_str_arr {
             ($e:expr) => {
                 $e.get(parser)
-                    .ok_or(Error::IncompatibleApi)?
+                    .ok_or(Error::incompatible())?
                     .children()
-                    .ok_or(Error::IncompatibleApi)?
+                    .ok_or(Error::incompatible())?
                     .all(parser)
                     .iter()
                     .map(|x| x.inner_text(parser))
             };
         }
-        let mapper =
-            FieldMapper::new(to_str_arr!(entries.next().ok_or(Error::IncompatibleApi)?))?;
+        let mapper = FieldMapper::new(to_str_arr!(entries.next().ok_or(Error::incompatible())?))?;
 
         let mut stocks = Vec::new();
         for entry in entries {
@@ -118,21 +129,40 @@ impl RawContent<'_> {
                 true => continue,
                 false => data.resize_with(FIELD_COUNT, || Cow::Borrowed("")),
             }
-            if let Ok(x) = mapper.map(&data.clone().try_into().unwrap()) {
-                stocks.push(x)
+            let Ok(data) = data.try_into() else {
+                continue;
+            };
+            if mapper.is_section(&data) {
+                continue;
+            }
+            match mapper.map(&data) {
+                Ok(x) if strict_industry && !x.industry.is_known() => {
+                    log::warn!("unknown industry `{}` of {}", x.industry, x.id.code);
+                    return Err(Error::incompatible());
+                }
+                Ok(x) => stocks.push(x),
+                Err(err) if mode == ParseMode::Strict => return Err(err),
+                Err(_) => {}
             }
         }
         Ok(stocks)
     }
 }
 
-fn big5_to_utf8(raw: Vec<u8>) -> String {
-    let c_string = CString::new(raw).unwrap();
-    let c_str = c_string.as_c_str();
+pub(crate) fn big5_to_utf8(raw: Vec<u8>) -> String {
+    // content after a nul byte is never part of the document
+    let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    let raw = &raw[..end];
 
-    BIG5.decode_without_bom_handling(c_str.to_bytes())
-        .0
-        .into_owned()
+    // a UTF-8 BOM means the response is mislabeled and actually UTF-8
+    if let Some(raw) = raw.strip_prefix(b"\xef\xbb\xbf") {
+        return String::from_utf8_lossy(raw).into_owned();
+    }
+    let content = BIG5.decode_without_bom_handling(raw).0;
+    content
+        .strip_prefix('\u{feff}')
+        .unwrap_or(&content)
+        .to_string()
 }
 
 #[cfg(test)]
@@ -142,7 +172,7 @@ mod tests {
     #[tokio::test]
     async fn parse() {
         let raw = RawContent(include_bytes!("../../test/C_public.jsp.html.small"));
-        let result = raw.parse().unwrap();
+        let result = raw.parse(ParseMode::Strict, false).unwrap();
         assert_eq!(result.len(), 3);
         assert_eq!(result[0].abbr, "太設");
     }
@@ -150,12 +180,73 @@ mod tests {
     #[ignore = "This test is time-consuming"]
     async fn parse_large() {
         let raw = RawContent(include_bytes!("../../test/C_public.jsp.html.large"));
-        for stock in raw.parse().unwrap() {
+        for stock in raw.parse(ParseMode::Strict, false).unwrap() {
             print!("{}, ", stock.abbr);
         }
         println!("...");
     }
     #[test]
+    fn malformed() {
+        for raw in [
+            &b""[..],
+            b"\0\0",
+            b"<tr><td>\0</td></tr>",
+            b"<table><tr></tr>",
+        ] {
+            assert!(RawContent(raw).parse(ParseMode::Strict, false).is_err());
+        }
+        let mut raw = include_bytes!("../../test/C_public.jsp.html.small").to_vec();
+        raw.extend_from_slice(b"\0\xff\xfe");
+        assert_eq!(
+            RawContent(&raw)
+                .parse(ParseMode::Strict, false)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+    #[test]
+    fn parse_mode() {
+        let mut raw = include_bytes!("../../test/C_public.jsp.html.small").to_vec();
+        let at = raw.windows(10).position(|x| x == b"1980/02/02").unwrap();
+        raw[at + 8..at + 10].copy_from_slice(b"30");
+        let raw = &raw[..];
+        assert!(RawContent(raw).parse(ParseMode::Strict, false).is_err());
+        assert_eq!(
+            RawContent(raw)
+                .parse(ParseMode::Lenient, false)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+    #[test]
+    fn bom() {
+        let raw = RawContent(include_bytes!("../../test/C_public.jsp.html.bom"));
+        let result = raw.parse(ParseMode::Strict, false).unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].abbr, "太設");
+        assert_eq!(big5_to_utf8(b"\xef\xbb\xbfabc".to_vec()), "abc");
+    }
+    #[test]
+    fn strict_industry() {
+        let raw = include_bytes!("../../test/C_public.jsp.html.small");
+        assert!(RawContent(raw).parse(ParseMode::Strict, true).is_ok());
+        let mut raw = raw.to_vec();
+        let (industry, _, _) = encoding_rs::BIG5.encode("建材營造業");
+        let at = raw
+            .windows(industry.len())
+            .position(|x| x == &industry[..])
+            .unwrap();
+        let (unknown, _, _) = encoding_rs::BIG5.encode("未知產業");
+        raw.splice(at..at + industry.len(), unknown.iter().copied());
+        assert!(RawContent(&raw).parse(ParseMode::Lenient, false).is_ok());
+        assert!(matches!(
+            RawContent(&raw).parse(ParseMode::Lenient, true),
+            Err(Error::IncompatibleApi { .. })
+        ));
+    }
+    #[test]
     fn test_big5_to_utf8() {
         let raw = include_bytes!("../../test/big5.test");
         let utf8 = big5_to_utf8(raw.to_vec());
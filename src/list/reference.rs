@@ -0,0 +1,131 @@
+//! In-memory reference data built from the stock list
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::RwLock;
+
+use super::{Industry, StockInfo};
+use crate::{Client, Error, Stock, StockKind};
+
+struct Snapshot {
+    fetched_at: Instant,
+    stocks: HashMap<Stock, StockInfo>,
+}
+
+/// Cached stock list of both markets, refreshed once `refresh_interval` elapsed
+///
+/// Refresh happens lazily on lookup, concurrent lookups share a single refresh.
+pub struct ReferenceData<'a> {
+    client: &'a Client,
+    refresh_interval: Duration,
+    cache: RwLock<Option<Snapshot>>,
+}
+
+impl Client {
+    /// Get the reference data cache, a day is a reasonable `refresh_interval`
+    pub fn reference_data(&self, refresh_interval: Duration) -> ReferenceData<'_> {
+        ReferenceData {
+            client: self,
+            refresh_interval,
+            cache: RwLock::new(None),
+        }
+    }
+}
+
+impl ReferenceData<'_> {
+    /// Look up the stock information, `None` if the stock is not listed
+    pub async fn info_of(&self, stock: &Stock) -> Result<Option<StockInfo>, Error> {
+        self.lookup(stock, StockInfo::clone).await
+    }
+    /// Look up the chinese name abbreviation
+    pub async fn name_of(&self, stock: &Stock) -> Result<Option<String>, Error> {
+        self.lookup(stock, |x| x.abbr.clone()).await
+    }
+    /// Look up the industry category
+    pub async fn industry_of(&self, stock: &Stock) -> Result<Option<Industry>, Error> {
+        self.lookup(stock, |x| x.industry.clone()).await
+    }
+    /// Refresh the cache regardless of `refresh_interval`
+    pub async fn refresh(&self) -> Result<(), Error> {
+        let snapshot = self.fetch().await?;
+        *self.cache.write().await = Some(snapshot);
+        Ok(())
+    }
+    fn is_fresh(&self, cache: &Option<Snapshot>) -> bool {
+        cache
+            .as_ref()
+            .is_some_and(|x| x.fetched_at.elapsed() < self.refresh_interval)
+    }
+    async fn lookup<T>(
+        &self,
+        stock: &Stock,
+        f: impl FnOnce(&StockInfo) -> T,
+    ) -> Result<Option<T>, Error> {
+        let cache = self.cache.read().await;
+        if self.is_fresh(&cache) {
+            return Ok(cache.as_ref().and_then(|x| x.stocks.get(stock)).map(f));
+        }
+        drop(cache);
+
+        let mut cache = self.cache.write().await;
+        // another lookup may have refreshed while waiting for the lock
+        if !self.is_fresh(&cache) {
+            *cache = Some(self.fetch().await?);
+        }
+        Ok(cache.as_ref().and_then(|x| x.stocks.get(stock)).map(f))
+    }
+    async fn fetch(&self) -> Result<Snapshot, Error> {
+        let list = self.client.list();
+        let mut stocks = HashMap::new();
+        for kind in [StockKind::Live, StockKind::OverTheCounter] {
+            for info in list.fetch(kind).await? {
+                stocks.insert(info.id.clone(), info);
+            }
+        }
+        Ok(Snapshot {
+            fetched_at: Instant::now(),
+            stocks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn lookup() {
+        let client = Client::new();
+        let reference = client.reference_data(Duration::from_secs(3600));
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let info = StockInfo {
+            id: stock.clone(),
+            abbr: "台積電".to_string(),
+            industry: Industry::Semiconductor,
+            ..Default::default()
+        };
+        *reference.cache.write().await = Some(Snapshot {
+            fetched_at: Instant::now(),
+            stocks: HashMap::from([(stock.clone(), info)]),
+        });
+        assert_eq!(
+            reference.name_of(&stock).await.unwrap().as_deref(),
+            Some("台積電")
+        );
+        assert_eq!(
+            reference.industry_of(&stock).await.unwrap(),
+            Some(Industry::Semiconductor)
+        );
+        let unknown = Stock {
+            kind: StockKind::Live,
+            code: 9999,
+        };
+        assert_eq!(reference.info_of(&unknown).await.unwrap(), None);
+    }
+}
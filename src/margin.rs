@@ -0,0 +1,168 @@
+//! TWSE margin trading (融資融券) balance API
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::table::{self, cell, DailyCache, Tables};
+use crate::{Client, Error, Family, MediaType, Stock, StockKind};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/exchangeReport/MI_MARGN";
+/// column headers of the stock table in `MI_MARGN` this module reads
+///
+/// `買進`, `賣出` and `今日餘額` appear twice, first for margin purchase then for short sale.
+pub(crate) const FIELDS: [&str; 7] = [
+    "代號",
+    "買進",
+    "賣出",
+    "今日餘額",
+    "買進",
+    "賣出",
+    "今日餘額",
+];
+
+/// Margin purchase and short sale of a stock in a day, in lots (張)
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MarginBalance {
+    /// Be noted that `date` is `UTC+8`
+    pub date: NaiveDate,
+    /// bought on margin (融資買進)
+    pub margin_buy: u64,
+    /// sold to repay margin (融資賣出)
+    pub margin_sell: u64,
+    /// margin purchase balance at the end of the day (融資今日餘額)
+    pub margin_balance: u64,
+    /// bought to cover short sale (融券買進)
+    pub short_buy: u64,
+    /// short sold (融券賣出)
+    pub short_sell: u64,
+    /// short sale balance at the end of the day (融券今日餘額)
+    pub short_balance: u64,
+}
+
+/// parse the report and return the balance of every stock of the day
+fn parse_report(body: &[u8], date: NaiveDate) -> Result<Vec<(Stock, MarginBalance)>, Error> {
+    let (table, columns) = table::parse_daily::<Tables>(body)?.find(FIELDS)?;
+    let [code, margin_buy, margin_sell, margin_balance, short_buy, short_sell, short_balance] =
+        columns;
+    table::listed_rows(&table.data, code, StockKind::Live)
+        .map(|row| {
+            let (stock, row) = row?;
+            let balance = MarginBalance {
+                date,
+                margin_buy: cell(row, margin_buy)?,
+                margin_sell: cell(row, margin_sell)?,
+                margin_balance: cell(row, margin_balance)?,
+                short_buy: cell(row, short_buy)?,
+                short_sell: cell(row, short_sell)?,
+                short_balance: cell(row, short_balance)?,
+            };
+            Ok((stock, balance))
+        })
+        .collect()
+}
+
+/// Wrapper of the [`Client`] facilitating margin trading api
+///
+/// Each day's report is cached, see [daily reports](crate#daily-reports).
+pub struct Margin<'a> {
+    client: &'a Client,
+    cache: DailyCache<MarginBalance>,
+}
+
+impl Client {
+    /// Get the margin trading API client
+    pub fn margin(&self) -> Margin<'_> {
+        Margin {
+            client: self,
+            cache: DailyCache::new(),
+        }
+    }
+}
+
+impl Margin<'_> {
+    /// Fetch the balance of every listed stock on a specific day
+    ///
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch_all(&self, date: NaiveDate) -> Result<Vec<(Stock, MarginBalance)>, Error> {
+        self.cache.get_or_fetch(date, self.fetch_report(date)).await
+    }
+    /// Fetch the balance of a stock on a specific day
+    ///
+    /// A stock not in the report, e.g. not eligible for margin trading, has every figure zero.
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch(&self, date: NaiveDate, stock: Stock) -> Result<MarginBalance, Error> {
+        Ok(
+            table::find(self.fetch_all(date).await?, &stock).unwrap_or(MarginBalance {
+                date,
+                ..Default::default()
+            }),
+        )
+    }
+    async fn fetch_report(&self, date: NaiveDate) -> Result<Vec<(Stock, MarginBalance)>, Error> {
+        let body = self
+            .client
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &date.format("%Y%m%d").to_string()),
+                    ("selectType", "ALL"),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+        parse_report(&body, date).map_err(|err| err.with_body(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::tests::{date, stock};
+
+    #[test]
+    fn parse() {
+        let data = parse_report(include_bytes!("../test/MI_MARGN.json"), date()).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].0, stock());
+        assert_eq!(
+            data[0].1,
+            MarginBalance {
+                date: date(),
+                margin_buy: 5216,
+                margin_sell: 3854,
+                margin_balance: 37218,
+                short_buy: 95,
+                short_sell: 120,
+                short_balance: 1628,
+            }
+        );
+        assert!(matches!(
+            parse_report(
+                r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#.as_bytes(),
+                date()
+            ),
+            Err(Error::MarketClosed)
+        ));
+    }
+    #[tokio::test]
+    async fn fetch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let margin = client.margin();
+        let data = margin.fetch(date(), stock()).await.unwrap();
+        assert_eq!(data.margin_balance, 37218);
+        let missing = margin.fetch(date(), Stock::live(9999)).await.unwrap();
+        assert_eq!(
+            missing,
+            MarginBalance {
+                date: date(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(server.hits("/exchangeReport/MI_MARGN"), 1);
+    }
+}
@@ -0,0 +1,442 @@
+//! Mock TWSE server for testing without network
+//!
+//! Enabled by `test-util` feature, it serves canned responses on localhost so
+//! downstream crates can test code built on `twstock` offline.
+//!
+//! # Example:
+//! ```rust,no_run
+//! use twstock::{mock::MockServer, *};
+//!
+//! async fn fetch() {
+//!     let server = MockServer::with_fixtures();
+//!     let client = server.client();
+//!     let data = client
+//!         .realtime()
+//!         .fetch(Stock {
+//!             kind: StockKind::Live,
+//!             code: 2330,
+//!         })
+//!         .await
+//!         .unwrap();
+//!     assert_eq!(data.name, "台積電");
+//!
+//!     // simulate rate limit
+//!     server.respond("/stock/api/getStockInfo.jsp", 429, "");
+//! }
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::Client;
+
+/// Canned upstream responses keyed by endpoint path
+pub static FIXTURES: [(&str, &[u8]); 12] = [
+    (
+        "/stock/api/getStockInfo.jsp",
+        include_bytes!("fixtures/getStockInfo.json"),
+    ),
+    (
+        "/exchangeReport/STOCK_DAY",
+        include_bytes!("fixtures/STOCK_DAY.json"),
+    ),
+    (
+        "/exchangeReport/FMSRFK",
+        include_bytes!("fixtures/FMSRFK.json"),
+    ),
+    (
+        "/exchangeReport/MI_INDEX",
+        include_bytes!("fixtures/MI_INDEX.json"),
+    ),
+    (
+        "/exchangeReport/TWT49U",
+        include_bytes!("fixtures/TWT49U.json"),
+    ),
+    (
+        "/www/zh-tw/afterTrading/dailyQ",
+        include_bytes!("fixtures/dailyQ.json"),
+    ),
+    (
+        "/www/zh-tw/afterTrading/tradingStock",
+        include_bytes!("fixtures/tradingStock.json"),
+    ),
+    (
+        "/exchangeReport/MI_MARGN",
+        include_bytes!("fixtures/MI_MARGN.json"),
+    ),
+    ("/rwd/zh/fund/T86", include_bytes!("fixtures/T86.json")),
+    (
+        "/rwd/zh/fund/MI_QFIIS",
+        include_bytes!("fixtures/MI_QFIIS.json"),
+    ),
+    (
+        "/rwd/zh/marginTrading/TWT93U",
+        include_bytes!("fixtures/TWT93U.json"),
+    ),
+    (
+        "/v1/opendata/t187ap03_L",
+        include_bytes!("fixtures/t187ap03_L.json"),
+    ),
+];
+
+#[derive(Debug, Clone)]
+struct Response {
+    status: u16,
+    location: Option<String>,
+    body: Vec<u8>,
+}
+
+#[derive(Default)]
+struct State {
+    /// queued responses of each path, the last one is kept to serve repeatedly
+    routes: HashMap<String, VecDeque<Response>>,
+    hits: HashMap<String, usize>,
+    connections: usize,
+}
+
+/// HTTP server on localhost serving queued responses by path
+///
+/// Unknown path is answered with `404`. Connections are kept alive so pooling of the client
+/// can be observed with [`MockServer::connections`]. The server stops when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Start a server without any route
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind mock server");
+        let addr = listener.local_addr().expect("failed to bind mock server");
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        state.lock().unwrap().connections += 1;
+                        let state = state.clone();
+                        std::thread::spawn(move || serve(stream, &state));
+                    }
+                }
+            })
+        };
+        Self {
+            addr,
+            state,
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+    /// Start a server answering every endpoint in [`FIXTURES`]
+    pub fn with_fixtures() -> Self {
+        let server = Self::start();
+        for (path, body) in FIXTURES {
+            server.respond(path, 200, body);
+        }
+        server
+    }
+    /// Base url of the server
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+    /// A [`Client`] sending every request to this server
+    pub fn client(&self) -> Client {
+        Client::new().with_base_url(self.url())
+    }
+    /// Answer every request on `path` with the response, replacing queued responses
+    pub fn respond(&self, path: &str, status: u16, body: impl Into<Vec<u8>>) -> &Self {
+        self.state.lock().unwrap().routes.remove(path);
+        self.enqueue(path, status, body)
+    }
+    /// Queue a response for `path`
+    ///
+    /// Queued responses are served in order, the last one is served repeatedly.
+    pub fn enqueue(&self, path: &str, status: u16, body: impl Into<Vec<u8>>) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        let queue = state.routes.entry(path.to_string()).or_default();
+        queue.push_back(Response {
+            status,
+            location: None,
+            body: body.into(),
+        });
+        self
+    }
+    /// Answer every request on `path` with `302 Found` to `location`
+    pub fn redirect(&self, path: &str, location: &str) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state.routes.insert(
+            path.to_string(),
+            VecDeque::from([Response {
+                status: 302,
+                location: Some(location.to_string()),
+                body: Vec::new(),
+            }]),
+        );
+        self
+    }
+    /// Number of TCP connections accepted so far
+    pub fn connections(&self) -> usize {
+        self.state.lock().unwrap().connections
+    }
+    /// Number of requests received on `path`
+    pub fn hits(&self, path: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state.hits.get(path).copied().unwrap_or_default()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // wake up the listener blocking on accept
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// serve requests on a kept-alive connection until the client closes it
+fn serve(mut stream: TcpStream, state: &Mutex<State>) -> std::io::Result<()> {
+    // don't keep the thread forever on an idle pooled connection
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    // headers and body are written separately, don't wait for delayed ACK in between
+    stream.set_nodelay(true)?;
+    while serve_one(&mut stream, state)? {}
+    Ok(())
+}
+
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// serve a single request, return `false` once the client closes the connection
+fn serve_one(stream: &mut TcpStream, state: &Mutex<State>) -> std::io::Result<bool> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|x| x == b"\r\n\r\n") {
+        let len = stream.read(&mut buf)?;
+        if len == 0 {
+            return Ok(false);
+        }
+        request.extend_from_slice(&buf[..len]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut line = request.split_whitespace();
+    let method = line.next().unwrap_or_default();
+    let path = line
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default();
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        *state.hits.entry(path.to_string()).or_default() += 1;
+        match state.routes.get_mut(path) {
+            Some(queue) if queue.len() > 1 => queue.pop_front(),
+            Some(queue) => queue.front().cloned(),
+            None => None,
+        }
+    }
+    .unwrap_or(Response {
+        status: 404,
+        location: None,
+        body: Vec::new(),
+    });
+
+    write!(stream, "HTTP/1.1 {} Mock\r\n", response.status)?;
+    if let Some(location) = &response.location {
+        write!(stream, "Location: {}\r\n", location)?;
+    }
+    if let Some(content_type) = content_type(&response.body) {
+        write!(stream, "Content-Type: {}\r\n", content_type)?;
+    }
+    write!(stream, "Content-Length: {}\r\n\r\n", response.body.len())?;
+    if method != "HEAD" {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()?;
+    Ok(true)
+}
+
+/// sniff the media type like upstream would send it
+fn content_type(body: &[u8]) -> Option<&'static str> {
+    match body.iter().find(|x| !x.is_ascii_whitespace()) {
+        Some(b'{' | b'[') => Some("application/json;charset=UTF-8"),
+        Some(b'"') => Some("text/csv;charset=MS950"),
+        Some(_) => Some("text/html"),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Month;
+
+    use super::*;
+    use crate::{Error, RetryPolicy, Stock, StockKind};
+
+    fn stock() -> Stock {
+        Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        }
+    }
+
+    #[tokio::test]
+    async fn fixtures() {
+        let server = MockServer::with_fixtures();
+        let client = server.client();
+        let data = client.realtime().fetch(stock()).await.unwrap();
+        assert_eq!(data.name, "台積電");
+        let data = client
+            .history()
+            .fetch(Month::January, 2021, stock())
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(server.hits("/exchangeReport/STOCK_DAY"), 1);
+    }
+    #[tokio::test]
+    async fn queued() {
+        let server = MockServer::start();
+        server
+            .enqueue("/stock/api/getStockInfo.jsp", 429, "")
+            .enqueue("/stock/api/getStockInfo.jsp", 200, FIXTURES[0].1);
+        let client = server.client();
+        assert!(matches!(
+            client.realtime().fetch(stock()).await,
+            Err(Error::RateLimitExceeded { attempts: 1 })
+        ));
+        assert!(client.realtime().fetch(stock()).await.is_ok());
+        assert!(client.realtime().fetch(stock()).await.is_ok());
+        assert_eq!(server.hits("/stock/api/getStockInfo.jsp"), 3);
+    }
+    #[tokio::test]
+    async fn retry_attempts() {
+        let server = MockServer::start();
+        server.respond("/stock/api/getStockInfo.jsp", 429, "");
+        let client = server.client().with_retry(2, Duration::from_millis(1));
+        assert!(matches!(
+            client.realtime().fetch(stock()).await,
+            Err(Error::RateLimitExceeded { attempts: 3 })
+        ));
+        assert_eq!(server.hits("/stock/api/getStockInfo.jsp"), 3);
+    }
+    #[tokio::test]
+    async fn shared_backoff() {
+        let server = MockServer::with_fixtures();
+        server
+            .respond("/exchangeReport/STOCK_DAY", 429, "")
+            .enqueue("/exchangeReport/STOCK_DAY", 200, FIXTURES[1].1);
+        let client = server
+            .client()
+            .with_report_retry(RetryPolicy::new(0, Duration::from_millis(200)));
+        assert!(matches!(
+            client.history().fetch(Month::January, 2021, stock()).await,
+            Err(Error::RateLimitExceeded { attempts: 1 })
+        ));
+        // another endpoint of the same family waits for the pause
+        let start = std::time::Instant::now();
+        client
+            .history()
+            .fetch_all(chrono::NaiveDate::from_ymd_opt(2024, 7, 15).unwrap())
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        // realtime family is not affected
+        let start = std::time::Instant::now();
+        client.realtime().fetch(stock()).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(200));
+    }
+    #[tokio::test]
+    async fn maintenance_page() {
+        let server = MockServer::start();
+        server.respond(
+            "/exchangeReport/STOCK_DAY",
+            200,
+            "<html><body>系統維護中</body></html>",
+        );
+        let client = server.client();
+        assert!(matches!(
+            client.history().fetch(Month::January, 2021, stock()).await,
+            Err(Error::IncompatibleApi { context }) if context.contains("系統維護中")
+        ));
+    }
+    #[tokio::test]
+    async fn gzip_without_header() {
+        let server = MockServer::start();
+        server.respond(
+            "/exchangeReport/STOCK_DAY",
+            200,
+            &[0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00][..],
+        );
+        assert!(matches!(
+            server
+                .client()
+                .history()
+                .fetch(Month::January, 2021, stock())
+                .await,
+            Err(Error::IncompatibleApi { .. })
+        ));
+    }
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn gzip_fixture_without_header() {
+        let server = MockServer::start();
+        server.respond(
+            "/exchangeReport/STOCK_DAY",
+            200,
+            &include_bytes!("fixtures/STOCK_DAY.json.gz")[..],
+        );
+        let data = server
+            .client()
+            .history()
+            .fetch(Month::January, 2021, stock())
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 5);
+    }
+    #[tokio::test]
+    async fn not_found() {
+        let server = MockServer::start();
+        assert!(matches!(
+            server.client().list().fetch(StockKind::Live).await,
+            Err(Error::UnexpectedStatus(reqwest::StatusCode::NOT_FOUND))
+        ));
+    }
+    #[tokio::test]
+    async fn server_error() {
+        let server = MockServer::start();
+        server.respond("/exchangeReport/STOCK_DAY", 503, "");
+        let client = server
+            .client()
+            .with_retry_policy(RetryPolicy::new(2, Duration::from_millis(1)));
+        let err = client
+            .history()
+            .fetch(Month::January, 2021, stock())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ServerError(x) if x.as_u16() == 503));
+        assert!(err.is_retryable());
+        // not retried as a rate limit
+        assert_eq!(server.hits("/exchangeReport/STOCK_DAY"), 1);
+    }
+}
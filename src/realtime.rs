@@ -1,105 +1,576 @@
 //! TWSE realtime data API
 
+use std::time::Duration;
+
 use super::*;
-use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone, Timelike};
+use futures_util::Stream;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio::time::{Interval, MissedTickBehavior};
+
+pub(crate) static ENDPOINT: &str = "https://mis.twse.com.tw/stock/api/getStockInfo.jsp";
+static ODD_LOT_ENDPOINT: &str = "https://mis.twse.com.tw/stock/api/getOddInfo.jsp";
+/// keys of a `msgArray` frame this module requires
+pub(crate) const FIELDS: [&str; 12] = [
+    "z", "tv", "v", "tlong", "d", "n", "o", "h", "l", "y", "u", "w",
+];
+
+/// Default number of stocks per request of [`RealTime::fetch_all`], keeping `ex_ch` short
+/// enough for upstream
+pub const FETCH_ALL_CHUNK_SIZE: usize = 50;
 
-static ENDPOINT: &str = "https://mis.twse.com.tw/stock/api/getStockInfo.jsp";
+/// Minimum polling interval of [`RealTime::watch`] unless
+/// [`Client::allow_aggressive_polling`] is set
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Clamp `interval` to [`MIN_POLL_INTERVAL`] unless aggressive polling is allowed
+fn clamp_interval(interval: Duration, aggressive: bool) -> Duration {
+    if aggressive || interval >= MIN_POLL_INTERVAL {
+        return interval;
+    }
+    log::warn!(
+        "polling interval {:?} is too aggressive, clamped to {:?}",
+        interval,
+        MIN_POLL_INTERVAL
+    );
+    MIN_POLL_INTERVAL
+}
+
+/// Time from `now` to the next multiple of `period` on the UTC+8 wall clock
+fn until_boundary(now: DateTime<FixedOffset>, period: Duration) -> Duration {
+    let period = period.as_millis().max(1);
+    let local = now.timestamp_millis() as i128 + now.offset().local_minus_utc() as i128 * 1000;
+    let elapsed = local.rem_euclid(period as i128) as u128;
+    Duration::from_millis(match elapsed {
+        0 => 0,
+        _ => (period - elapsed) as u64,
+    })
+}
 
 fn default_json_number() -> Value {
     Value::String("1".to_owned())
 }
 
+fn parse_number<T: std::str::FromStr>(value: &Value) -> Option<T> {
+    match value {
+        Value::Number(x) => x.to_string().parse().ok(),
+        Value::String(x) => x.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Largest `tlong` read as seconds, anything above is milliseconds
+///
+/// `tlong` is documented as milliseconds, but a 10-digit value in seconds shows up
+/// occasionally; no millisecond timestamp after 1973 falls below this.
+const SECONDS_THRESHOLD: i64 = 100_000_000_000;
+
+/// convert `tlong` to a datetime, accepting both seconds and milliseconds
+///
+/// `None` if out of range, the earlier instant if ambiguous.
+fn parse_timestamp(value: i64) -> Option<DateTime<FixedOffset>> {
+    match (-SECONDS_THRESHOLD..SECONDS_THRESHOLD).contains(&value) {
+        true => get_time_zone().timestamp_opt(value, 0).earliest(),
+        false => get_time_zone().timestamp_millis_opt(value).earliest(),
+    }
+}
+
+/// RFC 3339 with the `+08:00` offset, an instant sent in other offset is converted
+#[cfg(feature = "serde")]
+mod taipei_time {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &DateTime<FixedOffset>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let value = value.with_timezone(&crate::get_time_zone());
+        serializer.serialize_str(&value.to_rfc3339())
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<FixedOffset>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&value)
+            .map(|x| x.with_timezone(&crate::get_time_zone()))
+            .map_err(D::Error::custom)
+    }
+}
+
+/// `YYYY-MM-DD` of the `UTC+8` calendar
+#[cfg(feature = "serde")]
+mod taipei_date {
+    use chrono::NaiveDate;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%Y-%m-%d";
+
+    pub fn serialize<S: Serializer>(value: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.format(FORMAT).to_string())
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        NaiveDate::parse_from_str(&value, FORMAT).map_err(D::Error::custom)
+    }
+}
+
+/// first level of an underscore-delimited best-five list, e.g. `"120_35_44_"`
+fn first_level<T: std::str::FromStr>(value: &Option<String>) -> Option<T> {
+    value.as_ref()?.split('_').next()?.parse().ok()
+}
+
+/// [`tick_size`] with price and tick in hundredths of NT dollar
+fn tick_cents(cents: i64) -> i64 {
+    match cents {
+        i64::MIN..1_000 => 1,
+        1_000..5_000 => 5,
+        5_000..10_000 => 10,
+        10_000..50_000 => 50,
+        50_000..100_000 => 100,
+        _ => 500,
+    }
+}
+
+/// Minimum price increment of a stock quoted at `price` on TWSE
+///
+/// e.g. `0.5` for a price between 100 and 500. ETF and other securities follow a different
+/// table.
+pub fn tick_size(price: Price) -> Price {
+    price_from_cents(tick_cents(price_to_cents(price)))
+}
+
+/// `(price, volume)` of each level of an underscore-delimited best-five price and volume list
+///
+/// Empty, `-` or unparseable level is skipped, so a closed market gives an empty vec.
+fn depth(prices: &Option<String>, volumes: &Option<String>) -> Vec<(Price, u64)> {
+    let (Some(prices), Some(volumes)) = (prices, volumes) else {
+        return Vec::new();
+    };
+    prices
+        .split('_')
+        .zip(volumes.split('_'))
+        .filter_map(|(price, volume)| Some((price.parse().ok()?, volume.parse().ok()?)))
+        .collect()
+}
+
 /// realtime frame data from TWSE
+///
+/// MIS frames carry volumes but no turnover (成交金額), so there is no intraday VWAP, see
+/// [`DailyData::vwap`](crate::history::DailyData::vwap) for the daily one.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RealTimeData {
-    pub price: f64,
-    pub volume: u64,
-    pub history_volume: u64,
+    /// last traded price, `None` if there's no trade yet in today's session
+    pub price: Option<Price>,
+    /// `None` if upstream doesn't provide it (e.g. newly listed or thin stock)
+    pub volume: Option<u64>,
+    /// `None` if upstream doesn't provide it
+    pub history_volume: Option<u64>,
+    /// Always `+08:00`, serialized as RFC 3339 (e.g. `2024-07-15T13:30:00+08:00`)
+    #[cfg_attr(feature = "serde", serde(with = "taipei_time"))]
     pub update_at: DateTime<FixedOffset>,
-    /// Be noted that `recent_trading_date` is `UTC+8`
+    /// Be noted that `recent_trading_date` is a `UTC+8` calendar date, serialized as
+    /// `YYYY-MM-DD` without zone
+    #[cfg_attr(feature = "serde", serde(with = "taipei_date"))]
     pub recent_trading_date: NaiveDate,
+    /// time of the last trade (`UTC+8`), `None` if upstream doesn't provide it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub trade_time: Option<NaiveTime>,
     pub name: String,
-    pub opening_price: f64,
-    pub histroy_high_price: f64,
-    pub histroy_low_price: f64,
-    pub yesterday_closing_price: f64,
-    pub limit_up_price: f64,
-    pub limit_down_price: f64,
+    /// `None` if upstream doesn't provide it
+    pub opening_price: Option<Price>,
+    /// highest price of today's session so far, `None` if upstream doesn't provide it
+    #[cfg_attr(feature = "serde", serde(alias = "histroy_high_price"))]
+    pub session_high: Option<Price>,
+    /// lowest price of today's session so far, `None` if upstream doesn't provide it
+    #[cfg_attr(feature = "serde", serde(alias = "histroy_low_price"))]
+    pub session_low: Option<Price>,
+    pub yesterday_closing_price: Price,
+    pub limit_up_price: Price,
+    pub limit_down_price: Price,
+    /// trading session the quote belongs to
+    pub session: Session,
+    /// best-five bid `(price, volume)`, highest price first, empty if there's no bid
+    pub best_bid: Vec<(Price, u64)>,
+    /// best-five ask `(price, volume)`, lowest price first, empty if there's no ask
+    pub best_ask: Vec<(Price, u64)>,
+}
+
+impl RealTimeData {
+    /// Best ask - best bid, `None` if either side is empty
+    pub fn spread(&self) -> Option<Price> {
+        Some(self.best_ask.first()?.0 - self.best_bid.first()?.0)
+    }
+    /// [`RealTimeData::spread`] in basis points of the mid price
+    pub fn spread_bps(&self) -> Option<f64> {
+        let mid = price_to_f64(self.best_ask.first()?.0 + self.best_bid.first()?.0) / 2.0;
+        match mid > 0.0 {
+            true => Some(price_to_f64(self.spread()?) / mid * 10_000.0),
+            false => None,
+        }
+    }
+    /// Reference price (平盤價) limits and ticks are based on, which is yesterday's close
+    pub fn reference_price(&self) -> Price {
+        self.yesterday_closing_price
+    }
+    /// Every valid order price from `limit_down_price` to `limit_up_price` (inclusive),
+    /// stepping by [`tick_size`] of each price band
+    pub fn valid_prices(&self) -> Vec<Price> {
+        let up = price_to_cents(self.limit_up_price);
+        let mut price = price_to_cents(self.limit_down_price);
+        let mut result = Vec::new();
+        while price <= up {
+            result.push(price_from_cents(price));
+            let tick = tick_cents(price);
+            // limit may be off the tick of its band
+            price = (price / tick + 1) * tick;
+        }
+        result
+    }
+    /// Price range of today's session so far as `(session_low, session_high)`
+    ///
+    /// return `None` before the first trade of the session
+    pub fn intraday_range(&self) -> Option<(Price, Price)> {
+        Some((self.session_low?, self.session_high?))
+    }
+
+    /// quote of today taken before the regular session opens
+    fn is_before_open(&self) -> bool {
+        self.update_at.date_naive() == self.recent_trading_date
+            && self.update_at.time() < NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+    }
+}
+
+/// Trading session of a realtime quote
+///
+/// Volume and price of different sessions are not comparable, e.g. odd-lot volume is in shares
+/// instead of lots.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Session {
+    /// regular continuous trading (09:00 ~ 13:30)
+    #[default]
+    Regular,
+    /// odd-lot (零股) trading
+    OddLot,
+    /// after-hours fixed price trading (盤後定價, 14:00 ~ 14:30)
+    AfterHours,
+    /// call auction with indicative price only (試撮), e.g. pre-open
+    Auction,
+}
+
+/// Market index quoted by the MIS realtime API
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Index {
+    /// 發行量加權股價指數 (TAIEX)
+    Taiex,
+    /// 櫃買指數
+    Tpex,
+    /// 寶島股價指數
+    Formosa,
+}
+
+impl Index {
+    /// Channel string of the MIS realtime API, e.g. `tse_t00.tw`
+    pub fn to_twse_channel(&self) -> String {
+        match self {
+            Index::Taiex => "tse_t00.tw",
+            Index::Tpex => "otc_o00.tw",
+            Index::Formosa => "tse_FRMSA.tw",
+        }
+        .to_string()
+    }
+}
+
+/// Anything with a realtime quote, to drive a watchlist mixing stocks (and ETFs) with indices
+#[derive(Debug, Hash, Clone, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Quotable {
+    Stock(Stock),
+    Index(Index),
+}
+
+impl Quotable {
+    /// Channel string of the MIS realtime API
+    pub fn to_twse_channel(&self) -> String {
+        match self {
+            Quotable::Stock(x) => x.to_twse_channel(),
+            Quotable::Index(x) => x.to_twse_channel(),
+        }
+    }
+}
+
+impl From<Stock> for Quotable {
+    fn from(value: Stock) -> Self {
+        Quotable::Stock(value)
+    }
+}
+
+impl From<Index> for Quotable {
+    fn from(value: Index) -> Self {
+        Quotable::Index(value)
+    }
 }
 
 /// Raw frame data from TWSE
 #[derive(Debug, Serialize, Deserialize)]
 struct FrameData {
+    #[serde(rename = "c", default)]
+    code: String,
+    #[serde(rename = "ex", default)]
+    exchange: String,
+    #[serde(rename = "ch", default)]
+    channel: String,
+    #[serde(rename = "ts", default)]
+    trial: String,
+    /// item type, `t` for an index
+    #[serde(rename = "it", default)]
+    item_type: Option<String>,
+    /// microseconds of the match time `t`
+    #[serde(rename = "mt", default)]
+    match_micros: Option<String>,
     #[serde(rename = "z")]
     price: Value,
     #[serde(rename = "tv")]
     volume: Value,
     #[serde(rename = "v", default = "default_json_number")]
     history_volume: Value,
-    #[serde(rename = "tlong")]
+    #[serde(rename = "tlong", default)]
     update_at: Value,
     #[serde(rename = "d")]
     recent_trading_date: Value,
+    #[serde(rename = "t", default)]
+    trade_time: Option<String>,
     #[serde(rename = "n")]
     name: String,
     #[serde(rename = "o")]
     opening_price: Value,
     #[serde(rename = "h")]
-    histroy_high_price: Value,
+    session_high: Value,
     #[serde(rename = "l")]
-    histroy_low_price: Value,
+    session_low: Value,
     #[serde(rename = "y")]
     yesterday_closing_price: Value,
     #[serde(rename = "u")]
     limit_up_price: Value,
     #[serde(rename = "w")]
     limit_down_price: Value,
+    #[serde(rename = "pz", default)]
+    indicative_price: Option<Value>,
+    #[serde(rename = "ps", default)]
+    indicative_volume: Option<Value>,
+    #[serde(rename = "b", default)]
+    bid_prices: Option<String>,
+    #[serde(rename = "a", default)]
+    ask_prices: Option<String>,
+    #[serde(rename = "g", default)]
+    bid_volumes: Option<String>,
+    #[serde(rename = "f", default)]
+    ask_volumes: Option<String>,
+}
+
+/// Indicative auction figures published during the pre-open session (委託揭示)
+///
+/// MIS has no dedicated imbalance field, the unmatched volumes are the first level of the
+/// best five (`g` and `f`), which during the auction is what's left at the best bid and ask
+/// after matching at `indicative_price`.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrderImbalance {
+    /// price at which the opening auction would match right now
+    pub indicative_price: Price,
+    /// volume that would be matched at `indicative_price`
+    pub matched_volume: u64,
+    /// volume left on the best bid after matching, first level of `g`
+    pub unmatched_buy_volume: u64,
+    /// volume left on the best ask after matching, first level of `f`
+    pub unmatched_sell_volume: u64,
+}
+
+/// Pre-open session runs from 08:30 to 09:00 (UTC+8)
+fn is_pre_open(time: NaiveTime) -> bool {
+    let start = NaiveTime::from_hms_opt(8, 30, 0).unwrap();
+    let end = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+    (start..end).contains(&time)
+}
+
+/// After-hours fixed price session starts at 14:00 (UTC+8)
+fn is_after_hours(time: NaiveTime) -> bool {
+    time >= NaiveTime::from_hms_opt(14, 0, 0).unwrap()
+}
+
+impl FrameData {
+    /// Trading date of the quote (`d`)
+    fn recent_trading_date(&self) -> Result<NaiveDate, Error> {
+        parse_number::<u64>(&self.recent_trading_date)
+            .and_then(|x| NaiveDate::parse_from_str(&x.to_string(), "%Y%m%d").ok())
+            .ok_or(Error::incompatible())
+    }
+    /// Time of the quote from `tlong`, or from `d` and `t` if `tlong` is missing or zero
+    fn update_at(&self, recent_trading_date: NaiveDate) -> Result<DateTime<FixedOffset>, Error> {
+        // `tlong` is occasionally stale or missing while `d` and `t` are correct
+        let update_at = match &self.update_at {
+            Value::Null => None,
+            x => Some(parse_number::<i64>(x).ok_or(Error::incompatible())?).filter(|x| *x != 0),
+        };
+        match (update_at, self.trade_time()) {
+            (Some(x), _) => parse_timestamp(x),
+            (None, Some(time)) => recent_trading_date
+                .and_time(time)
+                .and_local_timezone(get_time_zone())
+                .earliest(),
+            (None, None) => None,
+        }
+        .ok_or(Error::incompatible())
+    }
+    /// Time of the last match (`t`)
+    fn trade_time(&self) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(self.trade_time.as_deref()?.trim(), "%H:%M:%S").ok()
+    }
+    /// Time of the last match to the microsecond (`t` and `mt`)
+    fn match_time(&self) -> Option<NaiveTime> {
+        let micros: u32 = self.match_micros.as_deref()?.trim().parse().ok()?;
+        self.trade_time()?
+            .with_nanosecond(micros.checked_mul(1000)?)
+    }
+    /// Session of the frame, regular unless upstream indicates otherwise
+    ///
+    /// Read from `it` and the match time of `t` and `mt`, the quote `time` is only used as a
+    /// guess for a frame without them.
+    fn session(&self, time: NaiveTime) -> Session {
+        let time = match (self.item_type.as_deref(), self.match_time()) {
+            // an index has no session of its own
+            (Some("t"), _) => return Session::Regular,
+            (Some(_), Some(matched)) => matched,
+            _ => time,
+        };
+        if self.channel.ends_with("_odd") {
+            Session::OddLot
+        } else if self.trial == "1" {
+            Session::Auction
+        } else if is_after_hours(time) {
+            Session::AfterHours
+        } else {
+            Session::Regular
+        }
+    }
+    /// Channel string of the frame, same as [`Quotable::to_twse_channel`]
+    fn channel_key(&self) -> String {
+        format!("{}_{}", self.exchange, self.channel)
+    }
+    /// Stock this frame belongs to
+    fn stock(&self) -> Option<Stock> {
+        let kind = match self.exchange.as_str() {
+            "tse" => StockKind::Live,
+            "otc" => StockKind::OverTheCounter,
+            _ => return None,
+        };
+        Some(Stock {
+            kind,
+            code: self.code.parse().ok()?,
+        })
+    }
+    /// Extract the indicative auction figures, `None` outside the pre-open session
+    fn order_imbalance(&self) -> Result<Option<OrderImbalance>, Error> {
+        let update_at = self.update_at(self.recent_trading_date()?)?;
+        if !is_pre_open(update_at.time()) {
+            return Ok(None);
+        }
+        let Some(indicative_price) = self.indicative_price.as_ref().and_then(parse_number) else {
+            return Ok(None);
+        };
+        Ok(Some(OrderImbalance {
+            indicative_price,
+            matched_volume: self
+                .indicative_volume
+                .as_ref()
+                .and_then(parse_number)
+                .unwrap_or_default(),
+            unmatched_buy_volume: first_level(&self.bid_volumes).unwrap_or_default(),
+            unmatched_sell_volume: first_level(&self.ask_volumes).unwrap_or_default(),
+        }))
+    }
 }
 
 impl TryFrom<FrameData> for RealTimeData {
     type Error = Error;
 
     fn try_from(value: FrameData) -> Result<Self, Self::Error> {
-        macro_rules! parse {
+        value.parse(ParseMode::Strict)
+    }
+}
+
+impl FrameData {
+    /// [`Error::MarketClosed`] if nothing is traded in the session, a missing `price` alone
+    /// only means no trade yet
+    fn parse(self, mode: ParseMode) -> Result<RealTimeData, Error> {
+        let data = self.parse_quote(mode)?;
+        if data.price.is_none()
+            && data.opening_price.is_none()
+            && data.session_high.is_none()
+            && data.session_low.is_none()
+        {
+            return Err(Error::MarketClosed);
+        }
+        Ok(data)
+    }
+    /// Same as [`FrameData::parse`], accepting a quote without any trade of the session
+    fn parse_quote(self, mode: ParseMode) -> Result<RealTimeData, Error> {
+        let value = self;
+        // `-` means the value is not available
+        macro_rules! try_parse {
+            ($f:ident,$t:ty) => {
+                match &value.$f {
+                    Value::String(x) if x.eq("-") => Ok(None),
+                    Value::Number(x) => <$t>::from_json_number(x)
+                        .map(Some)
+                        .ok_or(Error::incompatible()),
+                    Value::String(x) => x.parse().map(Some).map_err(|_| Error::incompatible()),
+                    _ => Err(Error::incompatible()),
+                }
+            };
+        }
+        macro_rules! parse_opt {
             ($f:ident,$t:ty) => {
-                paste::paste! {
-                    match value.$f{
-                        Value::Number(x) => x.[<as_ $t>](),
-                        Value::String(x) => {
-                            if x.eq("-"){
-                                return Err(Error::MarketClosed);
-                            }
-                            x.parse().ok()
-                        },
-                        _ => return Err(Error::IncompatibleApi),
-                    }.ok_or(Error::IncompatibleApi)?
+                match try_parse!($f, $t) {
+                    Err(_) if mode == ParseMode::Lenient => None,
+                    x => x?,
                 }
             };
         }
+        macro_rules! parse {
+            ($f:ident,$t:ty) => {
+                try_parse!($f, $t)?.ok_or(Error::incompatible())?
+            };
+        }
 
-        let update_at = get_time_zone()
-            .timestamp_millis_opt(parse!(update_at, i64))
-            .unwrap();
-        let recent_trading_date =
-            NaiveDate::parse_from_str(&parse!(recent_trading_date, u64).to_string(), "%Y%m%d")
-                .map_err(|_| Error::IncompatibleApi)?;
+        let recent_trading_date = value.recent_trading_date()?;
+        let trade_time = value.trade_time();
+        let update_at = value.update_at(recent_trading_date)?;
 
+        let session = value.session(update_at.time());
         Ok(RealTimeData {
-            price: parse!(price, f64),
-            volume: parse!(volume, u64),
-            history_volume: parse!(history_volume, u64),
+            price: try_parse!(price, Price)?,
+
+            volume: parse_opt!(volume, u64),
+            history_volume: parse_opt!(history_volume, u64),
             update_at,
             recent_trading_date,
+            trade_time,
             name: value.name,
-            opening_price: parse!(opening_price, f64),
-            histroy_high_price: parse!(histroy_high_price, f64),
-            histroy_low_price: parse!(histroy_low_price, f64),
-            yesterday_closing_price: parse!(yesterday_closing_price, f64),
-            limit_up_price: parse!(limit_up_price, f64),
-            limit_down_price: parse!(limit_down_price, f64),
+            opening_price: parse_opt!(opening_price, Price),
+            session_high: parse_opt!(session_high, Price),
+            session_low: parse_opt!(session_low, Price),
+            yesterday_closing_price: parse!(yesterday_closing_price, Price),
+            limit_up_price: parse!(limit_up_price, Price),
+            limit_down_price: parse!(limit_down_price, Price),
+            session,
+            best_bid: depth(&value.bid_prices, &value.bid_volumes),
+            best_ask: depth(&value.ask_prices, &value.ask_volumes),
         })
     }
 }
@@ -119,9 +590,32 @@ pub struct RealTime<'a>(&'a Client);
 
 impl Client {
     /// get realtime API client
-    pub fn realtime(&self) -> RealTime {
+    pub fn realtime(&self) -> RealTime<'_> {
         RealTime(self)
     }
+    /// Fetch realtime quote of a stock or an index
+    pub async fn quote(&self, item: Quotable) -> Result<RealTimeData, Error> {
+        let mut data = self.quote_batch(std::slice::from_ref(&item)).await?;
+        data.pop().ok_or(Error::incompatible())
+    }
+    /// Fetch realtime quote of stocks and indices in batch
+    ///
+    /// Result is in the same order as `items`.
+    /// Return [`Error::IncompatibleApi`] if upstream omits any of `items`.
+    pub async fn quote_batch(&self, items: &[Quotable]) -> Result<Vec<RealTimeData>, Error> {
+        let channels = items
+            .iter()
+            .map(Quotable::to_twse_channel)
+            .collect::<Vec<_>>();
+        let frames = self
+            .realtime()
+            .fetch_channels(ENDPOINT, channels.iter().cloned())
+            .await?;
+        reorder_by_channel(&channels, frames)?
+            .into_iter()
+            .map(|x| x.parse(self.parse_mode))
+            .collect()
+    }
 }
 
 impl RealTime<'_> {
@@ -131,59 +625,315 @@ impl RealTime<'_> {
     /// (1 request per 3 seconds with some burst at time of writing)
     pub async fn fetch(&self, stock: Stock) -> Result<RealTimeData, Error> {
         match self
-            .fetch_raw(std::iter::once(stock))
+            .fetch_raw(std::iter::once(&stock))
+            .await?
+            .into_iter()
+            .next()
+        {
+            Some(x) => x.parse(self.0.parse_mode),
+            None => Err(Error::incompatible()),
+        }
+    }
+    /// Fetch realtime data, tolerating missing price before the open
+    ///
+    /// Before 09:00 of a trading day, `price` is `None` while static fields (name, previous
+    /// close, limit up/down) are populated. Missing price at any other time (e.g. holiday)
+    /// is still [`Error::MarketClosed`].
+    pub async fn fetch_preopen_aware(&self, stock: Stock) -> Result<RealTimeData, Error> {
+        match self
+            .fetch_raw(std::iter::once(&stock))
+            .await?
+            .into_iter()
+            .next()
+        {
+            Some(x) => {
+                let data = x.parse_quote(self.0.parse_mode)?;
+                match data.price.is_none() && !data.is_before_open() {
+                    true => Err(Error::MarketClosed),
+                    false => Ok(data),
+                }
+            }
+            None => Err(Error::incompatible()),
+        }
+    }
+    /// Fetch the pre-open order imbalance (委託揭示)
+    ///
+    /// return `None` once the continuous session has started
+    pub async fn fetch_order_imbalance(
+        &self,
+        stock: Stock,
+    ) -> Result<Option<OrderImbalance>, Error> {
+        match self
+            .fetch_raw(std::iter::once(&stock))
             .await?
             .into_iter()
             .next()
         {
-            Some(x) => x.try_into(),
-            None => Err(Error::IncompatibleApi),
+            Some(x) => x.order_imbalance(),
+            None => Err(Error::incompatible()),
         }
     }
     /// Fetch realtime data from TWSE in batch
+    ///
+    /// Result is in the same order as `stocks`, so it's safe to zip them together.
+    /// Return [`Error::IncompatibleApi`] if upstream omits any of `stocks`.
     pub async fn fetch_batch(
         &self,
         stocks: impl Iterator<Item = Stock>,
     ) -> Result<Vec<RealTimeData>, Error> {
-        self.fetch_raw(stocks)
-            .await?
+        self.fetch_batch_slice(&stocks.collect::<Vec<_>>()).await
+    }
+    /// Same as [`RealTime::fetch_batch`], borrowing `stocks` so a watchlist can be reused
+    pub async fn fetch_batch_slice(&self, stocks: &[Stock]) -> Result<Vec<RealTimeData>, Error> {
+        let frames = self.fetch_raw(stocks.iter()).await?;
+        reorder(stocks, frames)?
             .into_iter()
-            .map(RealTimeData::try_from)
+            .map(|x| x.parse(self.0.parse_mode))
             .collect()
     }
-    async fn fetch_raw(
+    /// Fetch realtime data from TWSE in batch, pairing each of `stocks` with its data
+    ///
+    /// Data is `None` if upstream omits the stock (e.g. unknown or halted), result is in
+    /// the same order as `stocks`.
+    pub async fn fetch_batch_reconciled(
         &self,
         stocks: impl Iterator<Item = Stock>,
-    ) -> Result<Vec<FrameData>, Error> {
-        let stocks = stocks
-            .map(|stock| match stock.kind {
-                StockKind::Live => format!("tse_{}.tw", stock.code),
-                StockKind::OverTheCounter => format!("otc_{}.tw", stock.code),
+    ) -> Result<Vec<(Stock, Option<RealTimeData>)>, Error> {
+        let stocks = stocks.collect::<Vec<_>>();
+        let frames = self.fetch_raw(stocks.iter()).await?;
+        reconcile(&stocks, frames)
+            .into_iter()
+            .map(|(stock, frame)| {
+                let data = frame.map(|x| x.parse(self.0.parse_mode)).transpose()?;
+                Ok((stock, data))
+            })
+            .collect()
+    }
+    /// Fetch realtime data from TWSE in batch, pairing each of `stocks` with its own outcome
+    ///
+    /// Unlike [`RealTime::fetch_batch`], a stock failing to parse doesn't fail the others.
+    /// A stock upstream omits (e.g. unknown or delisted) is [`Error::UnknownStock`], result
+    /// is in the same order as `stocks`.
+    pub async fn fetch_batch_partial(
+        &self,
+        stocks: impl Iterator<Item = Stock>,
+    ) -> Result<Vec<(Stock, Result<RealTimeData, Error>)>, Error> {
+        let stocks = stocks.collect::<Vec<_>>();
+        let frames = self.fetch_raw(stocks.iter()).await?;
+        Ok(reconcile(&stocks, frames)
+            .into_iter()
+            .map(|(stock, frame)| {
+                let data = match frame {
+                    Some(x) => x.parse(self.0.parse_mode),
+                    None => Err(Error::UnknownStock(stock.code)),
+                };
+                (stock, data)
             })
-            .collect::<Vec<String>>()
-            .join("|");
+            .collect())
+    }
+    /// Fetch realtime data of every stock of `kind` in the stock list, see
+    /// [`RealTime::fetch_all_chunked`]
+    pub async fn fetch_all(&self, kind: StockKind) -> Result<Vec<RealTimeData>, Error> {
+        self.fetch_all_chunked(kind, FETCH_ALL_CHUNK_SIZE).await
+    }
+    /// Fetch realtime data of every stock of `kind` in the stock list, `chunk_size` stocks
+    /// per request
+    ///
+    /// Requests are sent one after another. A stock without quote (e.g. not traded yet, or
+    /// the market is closed) or omitted by upstream is left out, so the result may be
+    /// partial or empty.
+    pub async fn fetch_all_chunked(
+        &self,
+        kind: StockKind,
+        chunk_size: usize,
+    ) -> Result<Vec<RealTimeData>, Error> {
+        let stocks = self
+            .0
+            .list()
+            .fetch(kind)
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .unique()
+            .collect::<Vec<_>>();
+        let mut result = Vec::with_capacity(stocks.len());
+        for chunk in stocks.chunks(chunk_size.max(1)) {
+            for (_, data) in self.fetch_batch_partial(chunk.iter().cloned()).await? {
+                match data {
+                    Ok(x) => result.push(x),
+                    Err(Error::MarketClosed | Error::UnknownStock(_)) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+        Ok(result)
+    }
+    /// Poll realtime data of `stocks` every `interval`
+    ///
+    /// The stream is infinite, a failed poll is yielded as error without ending the stream,
+    /// so stop it with [`StreamExt::take`](futures_util::StreamExt::take) or
+    /// [`StreamExt::take_until`](futures_util::StreamExt::take_until).
+    /// `interval` shorter than [`MIN_POLL_INTERVAL`] is clamped, see
+    /// [`Client::allow_aggressive_polling`] to opt out.
+    pub fn watch(
+        &self,
+        stocks: Vec<Stock>,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Vec<RealTimeData>, Error>> + '_ {
+        let interval = tokio::time::interval(clamp_interval(interval, self.0.aggressive_polling));
+        self.poll(stocks, interval)
+    }
+    /// Subscribe to realtime data of `stocks`, fetched as one batch every `interval`
+    ///
+    /// The stream is infinite, stop it with
+    /// [`StreamExt::take`](futures_util::StreamExt::take) or
+    /// [`StreamExt::take_until`](futures_util::StreamExt::take_until). A failed poll is yielded
+    /// as error and the next tick polls again. Same as [`RealTime::watch`].
+    pub fn subscribe(
+        &self,
+        stocks: Vec<Stock>,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Vec<RealTimeData>, Error>> + '_ {
+        self.watch(stocks, interval)
+    }
+    /// Poll realtime data of `stocks` on every `interval` boundary of the TWSE clock (UTC+8)
+    ///
+    /// e.g. every 5 seconds lands on the 0/5/10... second mark, the first poll waits for
+    /// the next boundary. Otherwise the same as [`RealTime::watch`].
+    pub fn watch_aligned(
+        &self,
+        stocks: Vec<Stock>,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Vec<RealTimeData>, Error>> + '_ {
+        let period = clamp_interval(interval, self.0.aggressive_polling);
+        let now = chrono::Utc::now().with_timezone(&get_time_zone());
+        let start = tokio::time::Instant::now() + until_boundary(now, period);
+        self.poll(stocks, tokio::time::interval_at(start, period))
+    }
+    fn poll(
+        &self,
+        stocks: Vec<Stock>,
+        mut interval: Interval,
+    ) -> impl Stream<Item = Result<Vec<RealTimeData>, Error>> + '_ {
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        futures_util::stream::unfold(
+            (interval, stocks),
+            move |(mut interval, stocks)| async move {
+                interval.tick().await;
+                let result = self.fetch_batch_slice(&stocks).await;
+                Some((result, (interval, stocks)))
+            },
+        )
+    }
+    /// Fetch odd-lot (零股) realtime data from TWSE
+    ///
+    /// Be noted that volume of odd-lot quote is in shares instead of lots
+    pub async fn fetch_odd_lot(&self, stock: Stock) -> Result<RealTimeData, Error> {
+        match self
+            .fetch_raw_from(ODD_LOT_ENDPOINT, std::iter::once(&stock))
+            .await?
+            .into_iter()
+            .next()
+        {
+            Some(x) => x.parse(self.0.parse_mode).map(|x| RealTimeData {
+                session: Session::OddLot,
+                ..x
+            }),
+            None => Err(Error::incompatible()),
+        }
+    }
+    async fn fetch_raw(
+        &self,
+        stocks: impl Iterator<Item = &Stock>,
+    ) -> Result<Vec<FrameData>, Error> {
+        self.fetch_raw_from(ENDPOINT, stocks).await
+    }
+    async fn fetch_raw_from(
+        &self,
+        endpoint: &str,
+        stocks: impl Iterator<Item = &Stock>,
+    ) -> Result<Vec<FrameData>, Error> {
+        let channels = stocks
+            .map(|stock| stock.validate().map(|_| stock.to_twse_channel()))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.fetch_channels(endpoint, channels.into_iter()).await
+    }
+    async fn fetch_channels(
+        &self,
+        endpoint: &str,
+        channels: impl Iterator<Item = String>,
+    ) -> Result<Vec<FrameData>, Error> {
+        let channels = ex_ch(channels);
 
-        let res = self
+        let body = self
             .0
-             .0
-            .get(ENDPOINT)
-            .query(&[("ex_ch", stocks)])
-            .send()
+            .get(
+                Family::RealTime,
+                endpoint,
+                &[("ex_ch", &channels)],
+                MediaType::Json,
+            )
             .await?;
+        log::trace!("{}: {}", endpoint, String::from_utf8_lossy(&body));
+        parse_frames(&body).map_err(|err| err.with_body(&body))
+    }
+}
 
-        if !res.status().is_success() {
-            return Err(Error::RateLimitExceeded);
-        }
+/// `ex_ch` query value of `channels`, e.g. `tse_2330.tw|otc_6488.tw`
+fn ex_ch(channels: impl Iterator<Item = String>) -> String {
+    channels.collect::<Vec<String>>().join("|")
+}
 
-        let body = res.bytes().await?;
-        dbg!(&body);
-        match serde_json::from_slice::<MsgArray<FrameData>>(&body) {
-            Ok(x) => Ok(x.array),
-            Err(_) => {
-                let x: RawErrorMessage =
-                    serde_json::from_slice(body.as_ref()).map_err(|_| Error::IncompatibleApi)?;
-                Err(Error::StatMessage(x.stat))
-            }
+/// Pair each of `stocks` with its frame, `None` if upstream omits it
+fn reconcile(stocks: &[Stock], frames: Vec<FrameData>) -> Vec<(Stock, Option<FrameData>)> {
+    let mut frames = frames
+        .into_iter()
+        .map(|frame| (frame.stock(), frame))
+        .collect::<Vec<_>>();
+    stocks
+        .iter()
+        .map(|stock| {
+            let frame = frames
+                .iter()
+                .position(|(x, _)| x.as_ref() == Some(stock))
+                .map(|index| frames.swap_remove(index).1);
+            (stock.clone(), frame)
+        })
+        .collect()
+}
+
+/// Reorder `frames` to match the order of `stocks`
+fn reorder(stocks: &[Stock], frames: Vec<FrameData>) -> Result<Vec<FrameData>, Error> {
+    reconcile(stocks, frames)
+        .into_iter()
+        .map(|(_, frame)| frame.ok_or(Error::incompatible()))
+        .collect()
+}
+
+/// Reorder `frames` to match the order of `channels`
+fn reorder_by_channel(
+    channels: &[String],
+    mut frames: Vec<FrameData>,
+) -> Result<Vec<FrameData>, Error> {
+    channels
+        .iter()
+        .map(|channel| {
+            let index = frames
+                .iter()
+                .position(|x| x.channel_key() == *channel)
+                .ok_or(Error::incompatible())?;
+            Ok(frames.swap_remove(index))
+        })
+        .collect()
+}
+
+fn parse_frames(body: &[u8]) -> Result<Vec<FrameData>, Error> {
+    match serde_json::from_slice::<MsgArray<FrameData>>(body) {
+        Ok(x) => Ok(x.array),
+        Err(_) => {
+            let x: RawErrorMessage =
+                serde_json::from_slice(body).map_err(|_| Error::incompatible())?;
+            Err(Error::StatMessage(x.stat))
         }
     }
 }
@@ -212,18 +962,541 @@ mod tests {
         };
     }
     #[tokio::test]
+    async fn fetch_odd_lot() {
+        let server = crate::mock::MockServer::start();
+        // odd-lot volume is in shares
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace(r#""ch":"2330.tw""#, r#""ch":"2330.tw_odd""#)
+            .replace(r#""tv":"1520""#, r#""tv":"357""#);
+        server.enqueue("/stock/api/getOddInfo.jsp", 200, body);
+        let data = server
+            .client()
+            .realtime()
+            .fetch_odd_lot(Stock {
+                kind: StockKind::Live,
+                code: 2330,
+            })
+            .await
+            .unwrap();
+        assert_eq!(data.session, Session::OddLot);
+        assert_eq!(data.volume, Some(357));
+        assert_eq!(server.hits("/stock/api/getOddInfo.jsp"), 1);
+        assert_eq!(server.hits("/stock/api/getStockInfo.jsp"), 0);
+    }
+    #[tokio::test]
     async fn fetch_raw() {
         let client = Client::new();
         let data = client
             .realtime()
-            .fetch_raw(std::iter::once(Stock {
+            .fetch_raw(std::iter::once(&Stock {
                 kind: StockKind::Live,
                 code: 2330,
             }))
             .await
             .unwrap();
-        dbg!(&data);
         assert_eq!(data.len(), 1);
-        assert_eq!(data.get(0).unwrap().name, "台積電");
+        assert_eq!(data.first().unwrap().name, "台積電");
+    }
+    #[test]
+    fn ex_ch_query() {
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        assert_eq!(
+            ex_ch(std::iter::once(stock.to_twse_channel())),
+            "tse_2330.tw"
+        );
+        let otc = Stock {
+            kind: StockKind::OverTheCounter,
+            code: 6488,
+        };
+        assert_eq!(
+            ex_ch([stock, otc].iter().map(Stock::to_twse_channel)),
+            "tse_2330.tw|otc_6488.tw"
+        );
+    }
+    #[test]
+    fn tick() {
+        for (x, tick) in [
+            ("9.99", "0.01"),
+            ("10", "0.05"),
+            ("49.95", "0.05"),
+            ("50", "0.1"),
+            ("100", "0.5"),
+            ("500", "1"),
+            ("999", "1"),
+            ("1000", "5"),
+        ] {
+            assert_eq!(tick_size(price(x)), price(tick), "{}", x);
+        }
+        let frame = parse_frames(include_bytes!("../test/getStockInfo.json"))
+            .unwrap()
+            .remove(0);
+        let data = RealTimeData {
+            limit_down_price: price("99"),
+            limit_up_price: price("101"),
+            ..RealTimeData::try_from(frame).unwrap()
+        };
+        assert_eq!(data.reference_price(), price("987"));
+        let prices = data.valid_prices();
+        assert_eq!(prices[..3], ["99", "99.1", "99.2"].map(price));
+        assert_eq!(prices[9..], ["99.9", "100", "100.5", "101"].map(price));
+    }
+    #[test]
+    fn depth() {
+        let levels = |x: &str| Some(x.to_string());
+        assert_eq!(
+            super::depth(&levels("10.5_10.4_"), &levels("3_7_")),
+            [(price("10.5"), 3), (price("10.4"), 7)]
+        );
+        assert!(super::depth(&levels("-"), &levels("-")).is_empty());
+        assert!(super::depth(&levels(""), &levels("")).is_empty());
+        assert!(super::depth(&None, &levels("3_")).is_empty());
+    }
+    #[test]
+    fn spread() {
+        let frame = parse_frames(include_bytes!("../test/getStockInfo.json"))
+            .unwrap()
+            .remove(0);
+        let data = RealTimeData::try_from(frame).unwrap();
+        assert_eq!(data.spread(), Some(price("1")));
+        assert_eq!(data.spread_bps(), Some(1.0 / 1001.5 * 10_000.0));
+        assert_eq!(data.best_bid.len(), 5);
+        assert_eq!(data.best_bid[0], (price("1001"), 12));
+        assert_eq!(data.best_ask[4], (price("1006"), 455));
+        let data = RealTimeData {
+            best_ask: Vec::new(),
+            ..data
+        };
+        assert_eq!(data.spread(), None);
+        assert_eq!(data.spread_bps(), None);
+    }
+    #[test]
+    fn schema() {
+        let frame: Value =
+            serde_json::from_slice::<MsgArray<Value>>(include_bytes!("../test/getStockInfo.json"))
+                .unwrap()
+                .array
+                .remove(0);
+        for key in FIELDS {
+            assert!(frame.get(key).is_some(), "missing key {}", key);
+        }
+    }
+    #[test]
+    fn malformed_timestamp() {
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace("1721021400000", "9223372036854775807");
+        let frame = parse_frames(body.as_bytes()).unwrap().remove(0);
+        assert!(matches!(
+            RealTimeData::try_from(frame),
+            Err(Error::IncompatibleApi { .. })
+        ));
+        let body = body.replace("9223372036854775807", "-9223372036854775808");
+        let frame = parse_frames(body.as_bytes()).unwrap().remove(0);
+        assert!(matches!(
+            RealTimeData::try_from(frame),
+            Err(Error::IncompatibleApi { .. })
+        ));
+    }
+    #[test]
+    fn timestamp_unit() {
+        let millis = parse_timestamp(1721021400000).unwrap();
+        assert_eq!(parse_timestamp(1721021400), Some(millis));
+        assert_eq!(millis.timestamp(), 1721021400);
+        for out_of_range in [i64::MAX, i64::MIN] {
+            assert!(parse_timestamp(out_of_range).is_none());
+        }
+
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace("1721021400000", "1721021400");
+        let frame = parse_frames(body.as_bytes()).unwrap().remove(0);
+        assert_eq!(RealTimeData::try_from(frame).unwrap().update_at, millis);
+    }
+    #[test]
+    fn poll_interval() {
+        let fast = Duration::from_millis(100);
+        assert_eq!(clamp_interval(fast, false), MIN_POLL_INTERVAL);
+        assert_eq!(clamp_interval(fast, true), fast);
+        let slow = Duration::from_secs(5);
+        assert_eq!(clamp_interval(slow, false), slow);
+    }
+    #[test]
+    fn until_boundary() {
+        let at = |h, m, s, ms| {
+            get_time_zone()
+                .with_ymd_and_hms(2024, 7, 15, h, m, s)
+                .unwrap()
+                + chrono::Duration::milliseconds(ms)
+        };
+        let five = Duration::from_secs(5);
+        assert_eq!(
+            super::until_boundary(at(9, 0, 3, 250), five),
+            Duration::from_millis(1750)
+        );
+        assert_eq!(super::until_boundary(at(9, 0, 10, 0), five), Duration::ZERO);
+        // aligned to midnight of UTC+8, not of UTC
+        assert_eq!(
+            super::until_boundary(at(1, 0, 0, 0), Duration::from_secs(3 * 3600)),
+            Duration::from_secs(2 * 3600)
+        );
+    }
+    #[test]
+    fn session() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let regular = parse_frames(include_bytes!("../test/getStockInfo.json"))
+            .unwrap()
+            .remove(0);
+        assert_eq!(regular.session(time(13, 30)), Session::Regular);
+        // matched at 13:30, not a guess from the quote time
+        assert_eq!(regular.session(time(14, 30)), Session::Regular);
+        let frame = |from: &str, to: &str| {
+            let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+                .unwrap()
+                .replace(from, to);
+            parse_frames(body.as_bytes()).unwrap().remove(0)
+        };
+        let after_hours = frame(r#""t":"13:30:00""#, r#""t":"14:30:00""#);
+        assert_eq!(after_hours.session(time(13, 30)), Session::AfterHours);
+        let index = frame(r#""it":"12""#, r#""it":"t""#);
+        assert_eq!(index.session(time(14, 30)), Session::Regular);
+        // falls back to the quote time without `it` and `mt`
+        let unindicated = frame(r#""mt":"000000","#, "");
+        assert_eq!(unindicated.session(time(13, 30)), Session::Regular);
+        assert_eq!(unindicated.session(time(14, 30)), Session::AfterHours);
+        let preopen = parse_frames(include_bytes!("../test/getStockInfo.preopen.json"))
+            .unwrap()
+            .remove(0);
+        assert_eq!(preopen.session(time(8, 59)), Session::Auction);
+        let odd = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace(r#""ch":"2330.tw""#, r#""ch":"2330.tw_odd""#);
+        let odd = parse_frames(odd.as_bytes()).unwrap().remove(0);
+        assert_eq!(odd.session(time(13, 30)), Session::OddLot);
+    }
+    #[test]
+    fn batch_order() {
+        let stocks = [2330, 2317].map(|code| Stock {
+            kind: StockKind::Live,
+            code,
+        });
+        let frames = parse_frames(include_bytes!("../test/getStockInfo.batch.json")).unwrap();
+        assert_eq!(frames[0].code, "2317");
+        let frames = reorder(&stocks, frames).unwrap();
+        assert_eq!(frames[0].code, "2330");
+        assert_eq!(frames[1].code, "2317");
+
+        let frames = parse_frames(include_bytes!("../test/getStockInfo.json")).unwrap();
+        assert!(matches!(
+            reorder(&stocks, frames),
+            Err(Error::IncompatibleApi { .. })
+        ));
+    }
+    #[test]
+    fn intraday_range() {
+        let frame = parse_frames(include_bytes!("../test/getStockInfo.json"))
+            .unwrap()
+            .remove(0);
+        let data = RealTimeData::try_from(frame).unwrap();
+        assert_eq!(data.intraday_range(), Some((price("993"), price("1005"))));
+    }
+    #[test]
+    fn batch_missing() {
+        let stocks = [2330, 9999, 2317].map(|code| Stock {
+            kind: StockKind::Live,
+            code,
+        });
+        let frames = parse_frames(include_bytes!("../test/getStockInfo.batch.json")).unwrap();
+        let result = reconcile(&stocks, frames);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].1.as_ref().unwrap().code, "2330");
+        assert_eq!(result[1].0.code, 9999);
+        assert!(result[1].1.is_none());
+        assert_eq!(result[2].1.as_ref().unwrap().code, "2317");
+    }
+    #[tokio::test]
+    async fn fetch_batch_slice() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/stock/api/getStockInfo.jsp",
+            200,
+            &include_bytes!("../test/getStockInfo.batch.json")[..],
+        );
+        let client = server.client();
+        let watchlist = vec![Stock::live(2317), Stock::live(2330)];
+        for _ in 0..2 {
+            let data = client
+                .realtime()
+                .fetch_batch_slice(&watchlist)
+                .await
+                .unwrap();
+            assert_eq!(data[1].name, "台積電");
+        }
+        assert_eq!(server.hits("/stock/api/getStockInfo.jsp"), 2);
+    }
+    #[tokio::test]
+    async fn fetch_batch_partial() {
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.batch.json").to_vec())
+            .unwrap()
+            .replace(r#""y":"210.0000""#, r#""y":"?""#);
+        let server = crate::mock::MockServer::start();
+        server.respond("/stock/api/getStockInfo.jsp", 200, body);
+        let stocks = [2330, 9999, 2317].map(Stock::live);
+        let result = server
+            .client()
+            .realtime()
+            .fetch_batch_partial(stocks.into_iter())
+            .await
+            .unwrap();
+        assert_eq!(result[0].1.as_ref().unwrap().name, "台積電");
+        assert!(matches!(&result[1].1, Err(Error::UnknownStock(9999))));
+        assert_eq!(result[2].0.code, 2317);
+        assert!(result[2].1.is_err());
+    }
+    #[tokio::test]
+    async fn fetch_all() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/isin/C_public.jsp",
+            200,
+            &include_bytes!("../test/C_public.jsp.html.small")[..],
+        );
+        // only 2506 is quoted, 2509 is omitted
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace("2330", "2506");
+        server.respond("/stock/api/getStockInfo.jsp", 200, body);
+        let data = server
+            .client()
+            .realtime()
+            .fetch_all_chunked(StockKind::Live, 1)
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(server.hits("/stock/api/getStockInfo.jsp"), 2);
+    }
+    #[test]
+    fn without_tlong() {
+        let frame = parse_frames(include_bytes!("../test/getStockInfo.notlong.json"))
+            .unwrap()
+            .remove(0);
+        let data = RealTimeData::try_from(frame).unwrap();
+        let expected = parse_frames(include_bytes!("../test/getStockInfo.json"))
+            .unwrap()
+            .remove(0);
+        let expected = RealTimeData::try_from(expected).unwrap();
+        assert_eq!(data.trade_time, NaiveTime::from_hms_opt(13, 30, 0));
+        assert_eq!(data.update_at, expected.update_at);
+        assert_eq!(data.update_at.offset(), &get_time_zone());
+
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace(r#""tlong":"1721021400000""#, r#""tlong":"0""#);
+        let frame = parse_frames(body.as_bytes()).unwrap().remove(0);
+        assert_eq!(
+            RealTimeData::try_from(frame).unwrap().update_at,
+            expected.update_at
+        );
+    }
+    #[test]
+    fn partially_missing() {
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace(r#""v":"23560""#, r#""v":"-""#)
+            .replace(r#""h":"1005.0000""#, r#""h":"-""#);
+        let frame = parse_frames(body.as_bytes()).unwrap().remove(0);
+        let data = RealTimeData::try_from(frame).unwrap();
+        assert_eq!(data.price, Some(price("1001")));
+        assert_eq!(data.volume, Some(1520));
+        assert_eq!(data.history_volume, None);
+        assert_eq!(data.session_high, None);
+        assert_eq!(data.intraday_range(), None);
+    }
+    #[test]
+    fn parse_mode() {
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace(r#""h":"1005.0000""#, r#""h":"N/A""#);
+        let frame = || parse_frames(body.as_bytes()).unwrap().remove(0);
+        assert!(matches!(
+            frame().parse(ParseMode::Strict),
+            Err(Error::IncompatibleApi { .. })
+        ));
+        let data = frame().parse(ParseMode::Lenient).unwrap();
+        assert_eq!(data.session_high, None);
+        assert_eq!(data.session_low, Some(price("993")));
+    }
+    #[tokio::test]
+    async fn quote_batch() {
+        let server = crate::mock::MockServer::start();
+        server.respond(
+            "/stock/api/getStockInfo.jsp",
+            200,
+            &include_bytes!("../test/getStockInfo.index.json")[..],
+        );
+        let client = server.client();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let items = [Quotable::Index(Index::Taiex), Quotable::Stock(stock)];
+        let data = client.quote_batch(&items).await.unwrap();
+        assert_eq!(data[0].name, "發行量加權股價指數");
+        assert_eq!(data[0].price, Some(price("23916.93")));
+        assert_eq!(data[1].name, "台積電");
+        let data = client.quote(Index::Taiex.into()).await.unwrap();
+        assert_eq!(data.yesterday_closing_price, price("23696.64"));
+        assert!(matches!(
+            client.quote(Index::Tpex.into()).await,
+            Err(Error::IncompatibleApi { .. })
+        ));
+    }
+    #[tokio::test]
+    async fn watch() {
+        use futures_util::StreamExt;
+        let server = crate::mock::MockServer::start();
+        server
+            .enqueue("/stock/api/getStockInfo.jsp", 503, "")
+            .enqueue(
+                "/stock/api/getStockInfo.jsp",
+                200,
+                &include_bytes!("../test/getStockInfo.json")[..],
+            );
+        let client = server.client().allow_aggressive_polling();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        let result = client
+            .realtime()
+            .watch(vec![stock], Duration::from_millis(10))
+            .take(2)
+            .collect::<Vec<_>>()
+            .await;
+        // a failed poll doesn't end the stream
+        assert!(matches!(result[0], Err(Error::ServerError(_))));
+        assert_eq!(result[1].as_ref().unwrap()[0].name, "台積電");
+    }
+    #[tokio::test]
+    async fn subscribe() {
+        use futures_util::StreamExt;
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client().allow_aggressive_polling();
+        let result = client
+            .realtime()
+            .subscribe(vec![Stock::live(2330)], Duration::from_millis(10))
+            .take(3)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(result.len(), 3);
+        assert!(result
+            .iter()
+            .all(|x| x.as_ref().unwrap()[0].name == "台積電"));
+        assert_eq!(server.hits("/stock/api/getStockInfo.jsp"), 3);
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_offset() {
+        let data = parse_frames(include_bytes!("../test/getStockInfo.json"))
+            .unwrap()
+            .remove(0)
+            .parse(ParseMode::Strict)
+            .unwrap();
+        let json = serde_json::to_value(&data).unwrap();
+        assert!(json["update_at"].as_str().unwrap().ends_with("+08:00"));
+        assert_eq!(
+            json["recent_trading_date"],
+            data.recent_trading_date.format("%Y-%m-%d").to_string()
+        );
+        let back: RealTimeData = serde_json::from_value(json).unwrap();
+        assert_eq!(back, data);
+        assert_eq!(back.update_at.offset(), &get_time_zone());
+    }
+    #[test]
+    fn market_closed() {
+        let frame = parse_frames(include_bytes!("../test/getStockInfo.preopen.json"))
+            .unwrap()
+            .remove(0);
+        assert!(matches!(
+            RealTimeData::try_from(frame),
+            Err(Error::MarketClosed)
+        ));
+    }
+    #[test]
+    fn no_trade_yet() {
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.json").to_vec())
+            .unwrap()
+            .replace(r#""z":"1001.0000""#, r#""z":"-""#)
+            .replace(r#""tv":"1520""#, r#""tv":"-""#);
+        let frame = parse_frames(body.as_bytes()).unwrap().remove(0);
+        let data = RealTimeData::try_from(frame).unwrap();
+        assert_eq!(data.price, None);
+        assert_eq!(data.volume, None);
+        assert!(data.opening_price.is_some());
+    }
+    #[test]
+    fn preopen_aware() {
+        let frame = || {
+            parse_frames(include_bytes!("../test/getStockInfo.preopen.json"))
+                .unwrap()
+                .remove(0)
+        };
+        let data = frame().parse_quote(ParseMode::Strict).unwrap();
+        assert!(data.is_before_open());
+        assert_eq!(data.price, None);
+        assert_eq!(data.yesterday_closing_price, price("987"));
+        assert_eq!(data.limit_up_price, price("1085"));
+        assert_eq!(data.limit_down_price, price("889"));
+
+        // frame of last trading day, e.g. on a holiday
+        let mut frame = frame();
+        frame.recent_trading_date = Value::String("20240712".to_string());
+        assert!(!frame
+            .parse_quote(ParseMode::Strict)
+            .unwrap()
+            .is_before_open());
+    }
+    #[tokio::test]
+    async fn fetch_preopen_aware() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let stock = Stock {
+            kind: StockKind::Live,
+            code: 2330,
+        };
+        server.respond(
+            "/stock/api/getStockInfo.jsp",
+            200,
+            &include_bytes!("../test/getStockInfo.preopen.json")[..],
+        );
+        let data = client.realtime().fetch_preopen_aware(stock.clone()).await;
+        assert_eq!(data.unwrap().name, "台積電");
+        assert!(matches!(
+            client.realtime().fetch(stock).await,
+            Err(Error::MarketClosed)
+        ));
+    }
+    #[test]
+    fn order_imbalance() {
+        let frames = parse_frames(include_bytes!("../test/getStockInfo.preopen.json")).unwrap();
+        let imbalance = frames[0].order_imbalance().unwrap().unwrap();
+        assert_eq!(imbalance.indicative_price, price("990"));
+        assert_eq!(imbalance.matched_volume, 1234);
+        assert_eq!(imbalance.unmatched_buy_volume, 88);
+        assert_eq!(imbalance.unmatched_sell_volume, 120);
+        // `d` and `t` stand in for a missing `tlong`
+        let body = String::from_utf8(include_bytes!("../test/getStockInfo.preopen.json").to_vec())
+            .unwrap()
+            .replace(r#""tlong":"1721005195000","#, "");
+        let frames = parse_frames(body.as_bytes()).unwrap();
+        assert_eq!(frames[0].order_imbalance().unwrap(), Some(imbalance));
+    }
+    #[test]
+    fn order_imbalance_continuous() {
+        let frames = parse_frames(include_bytes!("../test/getStockInfo.json")).unwrap();
+        assert_eq!(frames[0].order_imbalance().unwrap(), None);
     }
 }
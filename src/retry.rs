@@ -0,0 +1,219 @@
+//! Retry policy for rate-limited requests
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+
+/// Exponential backoff with jitter applied when upstream rejects a request
+///
+/// The default policy never retries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// retries after the first attempt, `0` disables retry
+    pub max_retries: u32,
+    /// delay before the first retry, doubled on every subsequent retry
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Create a new retry policy
+    pub fn new(max_retries: u32, base_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay,
+        }
+    }
+    /// Preset for the MIS realtime endpoint
+    ///
+    /// MIS throttles per few seconds and recovers quickly, so retry soon and give up early.
+    pub fn realtime() -> Self {
+        Self::new(2, Duration::from_millis(500))
+    }
+    /// Preset for report endpoints
+    ///
+    /// Reports ban for longer once throttled, so back off patiently.
+    pub fn report() -> Self {
+        Self::new(5, Duration::from_secs(2))
+    }
+    /// Delay before the `attempt`-th retry (zero based), with up to 50% jitter
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(1 << attempt.min(16));
+        delay.saturating_add(delay.mul_f64(jitter() / 2.0))
+    }
+}
+
+/// Pause shared by every request of an endpoint family
+///
+/// Once a request is rate-limited, every in-flight retry and subsequent request waits for
+/// the same pause instead of retrying in isolation, which would prolong the ban.
+#[derive(Debug, Default)]
+pub(crate) struct SharedBackoff(Mutex<Option<Instant>>);
+
+impl SharedBackoff {
+    /// Wait until the current pause, if any, is over
+    pub(crate) async fn wait(&self) {
+        // pause may be extended while sleeping
+        while let Some(until) = self.until() {
+            tokio::time::sleep_until(until.into()).await;
+        }
+    }
+    /// Pause every request for `delay`, never shortening the current pause
+    pub(crate) fn pause(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut current = self.0.lock().unwrap();
+        *current = Some(current.map_or(until, |x| x.max(until)));
+    }
+    fn until(&self) -> Option<Instant> {
+        let mut current = self.0.lock().unwrap();
+        match *current {
+            Some(x) if x > Instant::now() => Some(x),
+            _ => {
+                *current = None;
+                None
+            }
+        }
+    }
+}
+
+/// Token bucket pacing every request of a client, `requests` per `per` with a burst of
+/// `requests`
+///
+/// Each caller reserves its slot under the lock and sleeps outside, so waiters are served
+/// in the order they arrived.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// time to refill a single token
+    interval: Duration,
+    /// how far ahead of schedule a request may go, i.e. the burst
+    burst: Duration,
+    /// when the bucket would be full again (theoretical arrival time)
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(requests: u32, per: Duration) -> Self {
+        let interval = per / requests.max(1);
+        Self {
+            interval,
+            burst: per.saturating_sub(interval),
+            next: Mutex::new(None),
+        }
+    }
+    /// Wait until a token is available and take it
+    pub(crate) async fn acquire(&self) {
+        let at = {
+            let now = Instant::now();
+            let mut next = self.next.lock().unwrap();
+            let tat = next.map_or(now, |x| x.max(now));
+            *next = Some(tat + self.interval);
+            tat.checked_sub(self.burst).unwrap_or(now)
+        };
+        tokio::time::sleep_until(at.into()).await;
+    }
+}
+
+/// Run `f` until it succeeds, backing off with `policy` between attempts
+///
+/// Only transient failures (see [`Error::is_retryable`]) are retried, any other error is
+/// returned immediately.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use twstock::*;
+///
+/// async fn fetch(client: &Client, stock: Stock) -> Result<Option<f64>, Error> {
+///     retry(RetryPolicy::new(3, Duration::from_secs(1)), || async {
+///         Ok(client.realtime().fetch(stock.clone()).await?.price)
+///     })
+///     .await
+/// }
+/// ```
+pub async fn retry<T, F, Fut>(policy: RetryPolicy, mut f: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Err(err) if err.is_retryable() && attempt < policy.max_retries => {
+                tokio::time::sleep(policy.delay(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// pseudo random number in `[0, 1)`, good enough to spread out retries
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.subsec_nanos());
+    (nanos % 1000) as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay() {
+        let policy = RetryPolicy::new(3, Duration::from_millis(100));
+        for (attempt, base) in [(0, 100), (1, 200), (2, 400)] {
+            let delay = policy.delay(attempt);
+            assert!(delay >= Duration::from_millis(base));
+            assert!(delay < Duration::from_millis(base * 3 / 2));
+        }
+        assert!(RetryPolicy::realtime().base_delay < RetryPolicy::report().base_delay);
+        assert!(RetryPolicy::realtime().max_retries < RetryPolicy::report().max_retries);
+    }
+    #[tokio::test]
+    async fn shared_backoff() {
+        let backoff = SharedBackoff::default();
+        let start = Instant::now();
+        backoff.wait().await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+        backoff.pause(Duration::from_millis(100));
+        backoff.pause(Duration::from_millis(10));
+        backoff.wait().await;
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+    #[tokio::test]
+    async fn retry() {
+        let policy = RetryPolicy::new(2, Duration::ZERO);
+        let mut calls = 0;
+        let result: Result<(), _> = super::retry(policy, || {
+            calls += 1;
+            async { Err(Error::RateLimitExceeded { attempts: 1 }) }
+        })
+        .await;
+        assert!(matches!(result, Err(Error::RateLimitExceeded { .. })));
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let result: Result<(), _> = super::retry(policy, || {
+            calls += 1;
+            async { Err(Error::MarketClosed) }
+        })
+        .await;
+        assert!(matches!(result, Err(Error::MarketClosed)));
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let result = super::retry(policy, || {
+            calls += 1;
+            let calls = calls;
+            async move {
+                match calls {
+                    1 => Err(Error::RateLimitExceeded { attempts: 1 }),
+                    _ => Ok(calls),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+    }
+}
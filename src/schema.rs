@@ -0,0 +1,60 @@
+//! Upstream schema this crate is built against
+
+use crate::{
+    company, dividend, foreign, history, institutional, list, margin, realtime, short_sale,
+};
+
+/// Column headers (or JSON keys) expected from each TWSE endpoint
+///
+/// Compare these against a live response to detect upstream changes early.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaInfo {
+    /// keys of each `msgArray` frame from `getStockInfo.jsp`
+    pub realtime: &'static [&'static str],
+    /// `fields` of `STOCK_DAY`
+    pub history: &'static [&'static str],
+    /// `fields` of the TPEx monthly trading table of an OTC stock (`tradingStock`)
+    pub history_tpex: &'static [&'static str],
+    /// `fields` of `FMSRFK`
+    pub history_summary: &'static [&'static str],
+    /// `fields` of the stock table in `MI_INDEX`
+    pub history_bulk: &'static [&'static str],
+    /// `fields` of the price index tables in `MI_INDEX`
+    pub history_index: &'static [&'static str],
+    /// `fields` of the TPEx daily quote table (`dailyQ`)
+    pub history_bulk_tpex: &'static [&'static str],
+    /// table headers of `C_public.jsp`
+    pub list: &'static [&'static str],
+    /// `fields` of `T86`
+    pub institutional: &'static [&'static str],
+    /// `fields` of `TWT93U`
+    pub short_sale: &'static [&'static str],
+    /// `fields` of the stock table in `MI_MARGN`
+    pub margin: &'static [&'static str],
+    /// `fields` of `MI_QFIIS`
+    pub foreign: &'static [&'static str],
+    /// `fields` of `TWT49U`
+    pub dividend: &'static [&'static str],
+    /// keys of each `t187ap03_L` record
+    pub company: &'static [&'static str],
+}
+
+/// Get the upstream schema this version of the crate understands
+pub fn supported_schema() -> SchemaInfo {
+    SchemaInfo {
+        realtime: &realtime::FIELDS,
+        history: &history::FIELDS,
+        history_tpex: &history::tpex::FIELDS,
+        history_summary: &history::summary::FIELDS,
+        history_bulk: &history::bulk::FIELDS,
+        history_index: &history::index::FIELDS,
+        history_bulk_tpex: &history::bulk::TPEX_FIELDS,
+        list: &list::parser::FIELDS,
+        institutional: &institutional::FIELDS,
+        short_sale: &short_sale::FIELDS,
+        margin: &margin::FIELDS,
+        foreign: &foreign::FIELDS,
+        dividend: &dividend::FIELDS,
+        company: &company::FIELDS,
+    }
+}
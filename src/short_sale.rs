@@ -0,0 +1,167 @@
+//! TWSE margin short sale and securities lending short sale (融券借券賣出) balance API
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::table::{self, cell, DailyCache, Report};
+use crate::{Client, Error, Family, MediaType, Stock, StockKind};
+
+pub(crate) static ENDPOINT: &str = "https://www.twse.com.tw/rwd/zh/marginTrading/TWT93U";
+/// column headers of `TWT93U` this module reads
+pub(crate) const FIELDS: [&str; 3] = ["代號", "今日餘額", "當日餘額"];
+
+/// Short sale balance of a stock at the end of a day, in shares
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShortBalance {
+    /// Be noted that `date` is `UTC+8`
+    pub date: NaiveDate,
+    /// balance of margin short sale (融券)
+    pub margin: u64,
+    /// balance of securities lending short sale (借券賣出)
+    pub lending: u64,
+}
+
+impl ShortBalance {
+    /// Total short interest of both channels
+    pub fn total(&self) -> u64 {
+        self.margin + self.lending
+    }
+}
+
+/// parse the report and return the balance of every stock of the day
+fn parse_report(body: &[u8], date: NaiveDate) -> Result<Vec<(Stock, ShortBalance)>, Error> {
+    let report: Report = table::parse_daily(body)?;
+    let [code, margin, lending] = report.columns(FIELDS)?;
+    table::listed_rows(&report.data, code, StockKind::Live)
+        .map(|row| {
+            let (stock, row) = row?;
+            let balance = ShortBalance {
+                date,
+                margin: cell(row, margin)?,
+                lending: cell(row, lending)?,
+            };
+            Ok((stock, balance))
+        })
+        .collect()
+}
+
+/// Wrapper of the [`Client`] facilitating short sale balance api
+///
+/// Each day's report is cached, see [daily reports](crate#daily-reports).
+pub struct ShortSale<'a> {
+    client: &'a Client,
+    cache: DailyCache<ShortBalance>,
+}
+
+impl Client {
+    /// Get the short sale balance API client
+    pub fn short_sale(&self) -> ShortSale<'_> {
+        ShortSale {
+            client: self,
+            cache: DailyCache::new(),
+        }
+    }
+}
+
+impl ShortSale<'_> {
+    /// Fetch the balance of every listed stock on a specific day
+    ///
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch_all(&self, date: NaiveDate) -> Result<Vec<(Stock, ShortBalance)>, Error> {
+        self.cache.get_or_fetch(date, self.fetch_report(date)).await
+    }
+    /// Fetch the balance of a stock on a specific day
+    ///
+    /// A stock not in the report had no short sale that day, so every balance is zero.
+    /// [`Error::MarketClosed`] if `date` is not a trading day.
+    pub async fn fetch(&self, date: NaiveDate, stock: Stock) -> Result<ShortBalance, Error> {
+        Ok(self.find(date, &stock).await?.unwrap_or(ShortBalance {
+            date,
+            ..Default::default()
+        }))
+    }
+    async fn find(&self, date: NaiveDate, stock: &Stock) -> Result<Option<ShortBalance>, Error> {
+        Ok(table::find(self.fetch_all(date).await?, stock))
+    }
+    /// Fetch the balance of a stock on every trading day between `from` and `to`
+    /// (inclusive), for charting short interest over time
+    ///
+    /// Holidays are skipped, so is a day the stock is not in the report.
+    pub async fn fetch_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        stock: Stock,
+    ) -> Result<Vec<ShortBalance>, Error> {
+        table::range(from, to, |date| self.find(date, &stock)).await
+    }
+    async fn fetch_report(&self, date: NaiveDate) -> Result<Vec<(Stock, ShortBalance)>, Error> {
+        let body = self
+            .client
+            .get(
+                Family::Report,
+                ENDPOINT,
+                &[
+                    ("response", "json"),
+                    ("date", &date.format("%Y%m%d").to_string()),
+                ],
+                MediaType::Json,
+            )
+            .await?;
+        parse_report(&body, date).map_err(|err| err.with_body(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::tests::{date, stock};
+
+    #[test]
+    fn parse() {
+        let data = parse_report(include_bytes!("../test/TWT93U.json"), date()).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0].0, stock());
+        assert_eq!(data[0].1.margin, 1628000);
+        assert_eq!(data[0].1.lending, 26042441);
+        assert_eq!(data[1].1.total(), 52702338);
+        assert!(matches!(
+            parse_report(
+                r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#.as_bytes(),
+                date()
+            ),
+            Err(Error::MarketClosed)
+        ));
+    }
+    #[tokio::test]
+    async fn fetch() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let short_sale = client.short_sale();
+        let data = short_sale.fetch(date(), stock()).await.unwrap();
+        assert_eq!(data.margin, 1628000);
+        let missing = short_sale.fetch(date(), Stock::live(9999)).await.unwrap();
+        assert_eq!(
+            missing,
+            ShortBalance {
+                date: date(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(server.hits("/rwd/zh/marginTrading/TWT93U"), 1);
+    }
+    #[tokio::test]
+    async fn fetch_range() {
+        let server = crate::mock::MockServer::with_fixtures();
+        let client = server.client();
+        let short_sale = client.short_sale();
+        let to = NaiveDate::from_ymd_opt(2024, 7, 21).unwrap();
+        let data = short_sale.fetch_range(date(), to, stock()).await.unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(data[4].date, NaiveDate::from_ymd_opt(2024, 7, 19).unwrap());
+        short_sale.fetch_range(date(), to, stock()).await.unwrap();
+        assert_eq!(server.hits("/rwd/zh/marginTrading/TWT93U"), 5);
+    }
+}
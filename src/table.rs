@@ -0,0 +1,273 @@
+//! Shared parsing of the `fields`/`data` tables TWSE and TPEx reports are made of
+//!
+//! A report module only supplies the headers it reads and how a row maps to its type.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::{is_no_data, strip_csv_guard, weekdays, Error, Stock, StockKind};
+
+/// A report of a single table, `fields` is required to tell it from a bare `stat`
+#[derive(Debug, Deserialize)]
+pub(crate) struct Report {
+    pub fields: Vec<String>,
+    #[serde(default)]
+    pub data: Vec<Vec<Value>>,
+}
+
+/// A report of many tables
+#[derive(Debug, Deserialize)]
+pub(crate) struct Tables {
+    /// `ok` if upstream has nothing to say about the data
+    #[serde(default)]
+    pub stat: String,
+    pub tables: Vec<Table>,
+}
+
+/// One of [`Tables`], absent parts of it are empty
+#[derive(Debug, Deserialize)]
+pub(crate) struct Table {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub fields: Vec<String>,
+    #[serde(default)]
+    pub data: Vec<Vec<Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawErrorMessage {
+    stat: String,
+}
+
+/// Deserialize the report, a rejected request becomes [`Error::StatMessage`]
+pub(crate) fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, Error> {
+    match serde_json::from_slice(body) {
+        Ok(x) => Ok(x),
+        Err(_) => {
+            let x: RawErrorMessage =
+                serde_json::from_slice(body).map_err(|_| Error::incompatible())?;
+            Err(Error::StatMessage(x.stat))
+        }
+    }
+}
+
+/// Same as [`parse_json`] for a daily report, no data becomes [`Error::MarketClosed`]
+pub(crate) fn parse_daily<T: DeserializeOwned>(body: &[u8]) -> Result<T, Error> {
+    parse_json(body).map_err(|err| match err {
+        Error::StatMessage(stat) if is_no_data(&stat) => Error::MarketClosed,
+        err => err,
+    })
+}
+
+/// Position of each of `names` in `fields`, whitespace in headers ignored
+///
+/// A name repeated in `names` maps to its next occurrence in `fields`, for tables like
+/// `MI_MARGN` having `買進` under both margin purchase and short sale.
+pub(crate) fn columns<const N: usize>(fields: &[String], names: [&str; N]) -> Option<[usize; N]> {
+    let mut map = [0; N];
+    for (i, name) in names.iter().enumerate() {
+        let nth = names[..i].iter().filter(|x| *x == name).count();
+        map[i] = fields
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.split_whitespace().collect::<String>() == *name)
+            .nth(nth)?
+            .0;
+    }
+    Some(map)
+}
+
+impl Report {
+    /// [`columns`] of the report, [`Error::IncompatibleApi`] if any is missing
+    pub(crate) fn columns<const N: usize>(&self, names: [&str; N]) -> Result<[usize; N], Error> {
+        columns(&self.fields, names).ok_or_else(|| Error::IncompatibleApi {
+            context: format!("fields {:?}", self.fields),
+        })
+    }
+}
+
+impl Tables {
+    /// The first table having all of `names`, with their [`columns`]
+    pub(crate) fn find<const N: usize>(
+        self,
+        names: [&str; N],
+    ) -> Result<(Table, [usize; N]), Error> {
+        self.tables
+            .into_iter()
+            .find_map(|x| Some((columns(&x.fields, names)?, x)))
+            .map(|(columns, table)| (table, columns))
+            .ok_or_else(Error::incompatible)
+    }
+}
+
+/// Text of a number cell, CSV guard, thousands separators and percent sign removed
+pub(crate) fn cell_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(x) => Some(x.to_string()),
+        Value::String(x) => Some(
+            strip_csv_guard(x.trim())
+                .trim()
+                .trim_end_matches('%')
+                .replace(',', ""),
+        ),
+        _ => None,
+    }
+}
+
+/// Parse a number cell like `1,234.50`, `"=\"12\""` or `73.52%`
+pub(crate) fn parse_cell<T: FromStr>(value: &Value) -> Option<T> {
+    cell_text(value)?.parse().ok()
+}
+
+/// [`parse_cell`] of column `i` of `row`
+pub(crate) fn cell<T: FromStr>(row: &[Value], i: usize) -> Result<T, Error> {
+    row.get(i).and_then(parse_cell).ok_or(Error::incompatible())
+}
+
+/// Rows of stocks in `data` with their [`Stock`], code read from column `code`
+///
+/// Warrants and other non-numeric code are skipped.
+pub(crate) fn listed_rows(
+    data: &[Vec<Value>],
+    code: usize,
+    kind: StockKind,
+) -> impl Iterator<Item = Result<(Stock, &[Value]), Error>> {
+    data.iter().filter_map(move |row| {
+        let Some(text) = row.get(code).and_then(Value::as_str) else {
+            return Some(Err(Error::incompatible()));
+        };
+        let text = strip_csv_guard(text.trim());
+        if !text.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let stock = text.parse().map(|code| Stock {
+            kind: kind.clone(),
+            code,
+        });
+        Some(
+            stock
+                .map(|stock| (stock, row.as_slice()))
+                .map_err(|_| Error::incompatible()),
+        )
+    })
+}
+
+/// Daily reports a wrapper has fetched, keyed by date
+pub(crate) struct DailyCache<T>(Mutex<HashMap<NaiveDate, Vec<(Stock, T)>>>);
+
+impl<T: Clone> DailyCache<T> {
+    pub(crate) fn new() -> Self {
+        Self(Mutex::new(HashMap::new()))
+    }
+    /// report of `date`, awaiting `fetch` only if it's not cached
+    pub(crate) async fn get_or_fetch(
+        &self,
+        date: NaiveDate,
+        fetch: impl Future<Output = Result<Vec<(Stock, T)>, Error>>,
+    ) -> Result<Vec<(Stock, T)>, Error> {
+        if let Some(x) = self.0.lock().await.get(&date) {
+            return Ok(x.clone());
+        }
+        let data = fetch.await?;
+        self.0.lock().await.insert(date, data.clone());
+        Ok(data)
+    }
+}
+
+/// row of `stock` in a daily report
+pub(crate) fn find<T>(report: Vec<(Stock, T)>, stock: &Stock) -> Option<T> {
+    report
+        .into_iter()
+        .find_map(|(x, data)| (x == *stock).then_some(data))
+}
+
+/// `fetch` every weekday between `from` and `to` (inclusive), skipping
+/// [`Error::MarketClosed`] and `None`
+pub(crate) async fn range<T, F>(
+    from: NaiveDate,
+    to: NaiveDate,
+    mut fetch: impl FnMut(NaiveDate) -> F,
+) -> Result<Vec<T>, Error>
+where
+    F: Future<Output = Result<Option<T>, Error>>,
+{
+    let mut result = Vec::new();
+    for date in weekdays(from, to) {
+        match fetch(date).await {
+            Ok(x) => result.extend(x),
+            Err(Error::MarketClosed) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Trading day most report fixtures are taken on
+    pub(crate) fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 7, 15).unwrap()
+    }
+    /// 台積電, present in every report fixture
+    pub(crate) fn stock() -> Stock {
+        Stock::live(2330)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_cell::<u64>(&json!("1,234")), Some(1234));
+        assert_eq!(parse_cell::<f64>(&json!("=\"12.5\"")), Some(12.5));
+        assert_eq!(parse_cell::<f64>(&json!(" 73.52% ")), Some(73.52));
+        assert_eq!(parse_cell::<i64>(&json!(-3)), Some(-3));
+        assert_eq!(parse_cell::<f64>(&json!("--")), None);
+        assert!(matches!(
+            parse_daily::<Report>(r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#.as_bytes()),
+            Err(Error::MarketClosed)
+        ));
+        assert!(matches!(
+            parse_json::<Report>(r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#.as_bytes()),
+            Err(Error::StatMessage(_))
+        ));
+    }
+    #[test]
+    fn repeated_columns() {
+        let fields = ["代號", "買進", "賣出", "買進", "賣出"].map(String::from);
+        assert_eq!(
+            columns(&fields, ["賣出", "買進", "買進", "代號"]),
+            Some([2, 1, 3, 0])
+        );
+        assert_eq!(columns(&fields, ["代號", "代號"]), None);
+        let fields = [" 成交 張數 "].map(String::from);
+        assert_eq!(columns(&fields, ["成交張數"]), Some([0]));
+    }
+    #[test]
+    fn listed() {
+        let data = vec![
+            vec![json!("2330"), json!("1")],
+            vec![json!("0050A"), json!("2")],
+            vec![json!("=\"2317\""), json!("3")],
+        ];
+        let rows = listed_rows(&data, 0, StockKind::Live)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, stock());
+        assert_eq!(rows[1].0, Stock::live(2317));
+        assert!(listed_rows(&[vec![json!(1)]], 0, StockKind::Live)
+            .next()
+            .unwrap()
+            .is_err());
+    }
+}